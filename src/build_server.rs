@@ -0,0 +1,115 @@
+use std::fs;
+use std::path::Path;
+use std::process::Command;
+
+use serde_json::Value;
+
+use crate::{derived_data_root, is_offline, retry};
+
+// Checks a generated buildServer.json against the workspace and scheme it was generated for.
+// Returns non-fatal warnings, or an error if the file is missing or points at the wrong project.
+pub fn validate(path: &Path, workspace: &str, scheme: &str) -> Result<Vec<String>, String> {
+    let contents = fs::read_to_string(path).map_err(|error| format!("{} was not written: {}", path.display(), error))?;
+    let json: Value = serde_json::from_str(&contents).map_err(|error| format!("{} is not valid JSON: {}", path.display(), error))?;
+    let mut warnings = Vec::new();
+
+    let expected_workspace = format!("{}.xcworkspace", workspace);
+    match json.get("workspace").and_then(Value::as_str) {
+        Some(found) if found.ends_with(&expected_workspace) => (),
+        Some(found) => return Err(format!("workspace is {}, expected {}", found, expected_workspace)),
+        None => return Err("missing workspace".to_string()),
+    }
+    match json.get("scheme").and_then(Value::as_str) {
+        Some(found) if found == scheme => (),
+        Some(found) => return Err(format!("scheme is {}, expected {}", found, scheme)),
+        None => return Err("missing scheme".to_string()),
+    }
+    match json.get("build_root").and_then(Value::as_str) {
+        Some(build_root) => {
+            let build_root = Path::new(build_root);
+            if !build_root.exists() {
+                warnings.push(format!("build_root {} does not exist yet, build the scheme once so sourcekit-lsp can find its index", build_root.display()));
+            } else if !build_root.starts_with(derived_data_root()) {
+                warnings.push(format!("build_root {} is outside of {}", build_root.display(), derived_data_root().display()));
+            }
+        }
+        None => return Err("missing build_root".to_string()),
+    }
+    Ok(warnings)
+}
+
+// sourcekit-lsp understands SwiftPM packages natively, it only needs any stale buildServer.json
+// out of the way and resolved dependencies to index against.
+pub fn setup_swiftpm(root: &Path) {
+    let build_server_path = root.join("buildServer.json");
+    if build_server_path.exists() {
+        say!("Removing stale {}", build_server_path.display());
+        retry::remove_file(&build_server_path);
+    }
+
+    let lsp_config_dir = root.join(".sourcekit-lsp");
+    let lsp_config_path = lsp_config_dir.join("config.json");
+    if !lsp_config_path.exists() {
+        say!("Writing {}", lsp_config_path.display());
+        let written = fs::create_dir_all(&lsp_config_dir)
+            .and_then(|_| fs::write(&lsp_config_path, "{\n  \"swiftPM\": {\n    \"configuration\": \"debug\"\n  }\n}\n"));
+        if let Err(error) = written {
+            warn!("Error: {}", error);
+        }
+    }
+
+    let mut command = Command::new("swift");
+    command.args(["package", "resolve"]).current_dir(root);
+    if is_offline() {
+        command.args(["--skip-update", "--disable-automatic-resolution"]);
+    }
+    let output = retry::network_output(&mut command).expect("failed to execute process");
+    if !output.status.success() {
+        panic!("swift package resolve failed: {}", String::from_utf8_lossy(&output.stderr));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::path::PathBuf;
+
+    fn build_server_json(name: &str, contents: &str) -> PathBuf {
+        let path = std::env::temp_dir().join(format!("sass-build-server-{}-{}.json", std::process::id(), name));
+        fs::write(&path, contents).expect("Could not write buildServer.json");
+        path
+    }
+
+    #[test]
+    fn accepts_a_matching_file_and_warns_about_a_missing_build_root() {
+        let path = build_server_json("valid", r#"{"workspace": "/src/App.xcworkspace", "scheme": "App", "build_root": "/nonexistent/DerivedData/App-abc"}"#);
+        let warnings = validate(&path, "App", "App").expect("Should be valid");
+        assert_eq!(warnings.len(), 1);
+        assert!(warnings[0].contains("does not exist yet"), "{:?}", warnings);
+    }
+
+    #[test]
+    fn rejects_the_wrong_workspace() {
+        let path = build_server_json("workspace", r#"{"workspace": "/src/Other.xcworkspace", "scheme": "App", "build_root": "/tmp"}"#);
+        assert_eq!(validate(&path, "App", "App"), Err("workspace is /src/Other.xcworkspace, expected App.xcworkspace".to_string()));
+    }
+
+    #[test]
+    fn rejects_the_wrong_scheme() {
+        let path = build_server_json("scheme", r#"{"workspace": "/src/App.xcworkspace", "scheme": "Widgets", "build_root": "/tmp"}"#);
+        assert_eq!(validate(&path, "App", "App"), Err("scheme is Widgets, expected App".to_string()));
+    }
+
+    #[test]
+    fn rejects_a_missing_build_root() {
+        let path = build_server_json("build-root", r#"{"workspace": "/src/App.xcworkspace", "scheme": "App"}"#);
+        assert_eq!(validate(&path, "App", "App"), Err("missing build_root".to_string()));
+    }
+
+    #[test]
+    fn rejects_invalid_json() {
+        let path = build_server_json("invalid", "{\"workspace\": ");
+        let error = validate(&path, "App", "App").unwrap_err();
+        assert!(error.contains("is not valid JSON"), "{}", error);
+    }
+}
@@ -0,0 +1,86 @@
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+use std::time::Duration;
+
+use serde::{Deserialize, Serialize};
+
+use crate::{run_streamed_with_timeout, Config};
+
+// A [[deps_scripts]] entry
+#[derive(Serialize, Deserialize, Clone, Debug, Default, PartialEq)]
+#[serde(default)]
+pub struct DepsScript {
+    // Relative to the git root, or absolute
+    pub path: String,
+    // Defaults to sh
+    pub interpreter: Option<String>,
+    pub args: Vec<String>,
+    pub env: HashMap<String, String>,
+    // Relative to the git root, defaults to the git root
+    pub working_directory: Option<String>,
+    pub timeout_seconds: Option<u64>,
+}
+
+impl DepsScript {
+    // Older configs spell paths inside the repo with a leading slash, so those resolve against the git root too
+    pub fn resolved_path(&self, gitroot: &str) -> PathBuf {
+        resolve(&self.path, gitroot)
+    }
+
+    fn run(&self, gitroot: &str) {
+        let path = self.resolved_path(gitroot);
+        let working_directory = self.working_directory.as_deref().map(|dir| resolve(dir, gitroot)).unwrap_or_else(|| PathBuf::from(gitroot));
+        let interpreter = self.interpreter.as_deref().unwrap_or("sh");
+        say!("Running {} {}", interpreter, path.display());
+
+        let mut command = Command::new(interpreter);
+        command.arg(&path).args(&self.args).envs(&self.env).current_dir(working_directory);
+        match run_streamed_with_timeout(&mut command, self.timeout_seconds.map(Duration::from_secs)) {
+            Ok((status, _)) if status.success() => (),
+            Ok((status, _)) => panic!("Deps script {} failed with {}", path.display(), status),
+            Err(error) => panic!("Deps script {} failed: {}", path.display(), error),
+        }
+    }
+}
+
+fn resolve(path: &str, gitroot: &str) -> PathBuf {
+    let absolute = Path::new(path);
+    if absolute.is_absolute() && absolute.exists() {
+        absolute.to_path_buf()
+    } else {
+        Path::new(gitroot).join(path.trim_start_matches('/'))
+    }
+}
+
+// post_install_script_location first, then [[deps_scripts]] in order
+pub fn configured(config: &Config) -> Vec<DepsScript> {
+    let mut scripts = Vec::new();
+    if let Some(location) = &config.post_install_script_location {
+        scripts.push(DepsScript { path: location.clone(), ..Default::default() });
+    }
+    scripts.extend(config.deps_scripts.iter().cloned());
+    scripts
+}
+
+// Runs each script in order, stopping at the first one that fails
+pub fn run_all(config: &Config, gitroot: &str) -> bool {
+    let scripts = configured(config);
+    for script in &scripts {
+        script.run(gitroot);
+    }
+    !scripts.is_empty()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn resolves_paths_against_the_git_root() {
+        assert_eq!(resolve("scripts/setup.sh", "/src/app"), PathBuf::from("/src/app/scripts/setup.sh"));
+        // A leading slash from older configs, when no such absolute path exists
+        assert_eq!(resolve("/scripts/setup.sh", "/src/app"), PathBuf::from("/src/app/scripts/setup.sh"));
+        assert_eq!(resolve("/bin/sh", "/src/app"), PathBuf::from("/bin/sh"));
+    }
+}
@@ -0,0 +1,147 @@
+use std::fmt;
+use std::path::Path;
+use std::process::Command;
+
+use serde_json::{json, Value};
+
+use crate::{deps_scripts, git_root, load_config, report, xcodebuild, Config};
+
+#[derive(PartialEq, Clone, Copy)]
+enum Status {
+    Pass,
+    Warn,
+    Fail,
+}
+
+impl fmt::Display for Status {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let name = match self {
+            Status::Pass => "pass",
+            Status::Warn => "warn",
+            Status::Fail => "fail",
+        };
+        write!(f, "{}", name)
+    }
+}
+
+struct Check {
+    name: String,
+    status: Status,
+    detail: String,
+}
+
+impl Check {
+    fn new(name: &str, status: Status, detail: String) -> Check {
+        Check { name: name.to_string(), status, detail }
+    }
+}
+
+// (name, program, version args, required). Missing optional tools only matter for some commands.
+const TOOLS: [(&str, &str, &[&str], bool); 9] = [
+    ("xcodebuild", "xcodebuild", &["-version"], true),
+    ("xcode-build-server", "xcode-build-server", &["--version"], true),
+    ("xcrun simctl", "xcrun", &["--find", "simctl"], true),
+    ("swift", "swift", &["--version"], true),
+    ("pod", "pod", &["--version"], false),
+    ("bundle", "bundle", &["--version"], false),
+    ("gem", "gem", &["--version"], false),
+    ("git", "git", &["--version"], true),
+    ("sccache", "sccache", &["--version"], false),
+];
+
+pub fn doctor() {
+    let mut checks: Vec<Check> = TOOLS.iter().map(|(name, program, args, required)| check_tool(name, program, args, *required)).collect();
+    checks.extend(check_project());
+
+    if report::json_enabled() {
+        let checks: Vec<Value> = checks.iter().map(|check| json!({
+            "name": check.name,
+            "status": check.status.to_string(),
+            "detail": check.detail,
+        })).collect();
+        report::result("doctor", json!({ "checks": checks }));
+    } else {
+        let name_width = checks.iter().map(|check| check.name.len()).max().unwrap_or(0);
+        for check in &checks {
+            say!("[{}] {:width$}  {}", check.status, check.name, check.detail, width = name_width);
+        }
+    }
+    let failures = checks.iter().filter(|check| check.status == Status::Fail).count();
+    if failures > 0 {
+        panic!("{} checks failed", failures);
+    }
+}
+
+fn check_tool(name: &str, program: &str, args: &[&str], required: bool) -> Check {
+    let missing_status = if required { Status::Fail } else { Status::Warn };
+    match Command::new(program).args(args).output() {
+        Ok(output) if output.status.success() => {
+            let stdout = String::from_utf8_lossy(&output.stdout);
+            let stderr = String::from_utf8_lossy(&output.stderr);
+            // Some tools print their version to stderr
+            let version = stdout.lines().chain(stderr.lines()).find(|line| !line.trim().is_empty()).unwrap_or("unknown version");
+            Check::new(name, Status::Pass, version.trim().to_string())
+        }
+        Ok(output) => Check::new(name, missing_status, format!("exited with {}", output.status)),
+        Err(error) => Check::new(name, missing_status, format!("not found: {}", error)),
+    }
+}
+
+fn check_project() -> Vec<Check> {
+    let mut checks = Vec::new();
+    let gitroot = git_root();
+    if gitroot.is_empty() {
+        checks.push(Check::new("git root", Status::Fail, "not inside a git repository".to_string()));
+        return checks;
+    }
+    checks.push(Check::new("git root", Status::Pass, gitroot.clone()));
+
+    let config: Config = load_config();
+    let workspace_file = format!("{}.xcworkspace", config.workspace_name);
+    let workspace_path = Path::new(&gitroot).join(&workspace_file);
+    let workspace_exists = workspace_path.exists();
+    checks.push(if config.workspace_name.is_empty() {
+        Check::new("workspace", Status::Warn, "workspace_name not configured".to_string())
+    } else if workspace_exists {
+        Check::new("workspace", Status::Pass, workspace_path.display().to_string())
+    } else {
+        Check::new("workspace", Status::Fail, format!("{} does not exist", workspace_path.display()))
+    });
+
+    checks.push(if config.scheme.is_empty() {
+        Check::new("scheme", Status::Warn, "scheme not configured".to_string())
+    } else if !workspace_exists {
+        Check::new("scheme", Status::Warn, "cannot check without a workspace".to_string())
+    } else {
+        match workspace_schemes(&config, &gitroot, &workspace_file) {
+            Some(schemes) if schemes.contains(&config.scheme) => Check::new("scheme", Status::Pass, config.scheme.clone()),
+            Some(schemes) => Check::new("scheme", Status::Fail, format!("{} not found, available: {}", config.scheme, schemes.join(", "))),
+            None => Check::new("scheme", Status::Warn, "could not list the workspace's schemes".to_string()),
+        }
+    });
+
+    let scripts = deps_scripts::configured(&config);
+    if scripts.is_empty() {
+        checks.push(Check::new("deps script", Status::Pass, "not configured".to_string()));
+    }
+    for script in scripts {
+        let script_path = script.resolved_path(&gitroot);
+        checks.push(if script_path.is_file() {
+            Check::new("deps script", Status::Pass, script_path.display().to_string())
+        } else {
+            Check::new("deps script", Status::Fail, format!("{} does not exist", script_path.display()))
+        });
+    }
+    checks
+}
+
+fn workspace_schemes(config: &Config, gitroot: &str, workspace_file: &str) -> Option<Vec<String>> {
+    let output = xcodebuild(&config.xcodebuild_args, &config.build_settings)
+        .args(["-list", "-json", "-workspace", workspace_file])
+        .current_dir(gitroot)
+        .output()
+        .ok()?;
+    let json: Value = serde_json::from_slice(&output.stdout).ok()?;
+    let schemes = json.get("workspace")?.get("schemes")?.as_array()?;
+    Some(schemes.iter().filter_map(|scheme| scheme.as_str().map(str::to_string)).collect())
+}
@@ -0,0 +1,54 @@
+use std::env;
+use std::process::Command;
+use std::sync::Mutex;
+
+use crate::{git_root, load_config, report, run_streamed, Config};
+
+// Whether every build this run succeeded, None until something is built
+static BUILD_STATUS: Mutex<Option<bool>> = Mutex::new(None);
+
+pub fn record_build(success: bool) {
+    let mut status = BUILD_STATUS.lock().expect("Build status lock poisoned");
+    *status = Some(status.unwrap_or(true) && success);
+}
+
+// Runs the shell commands configured under [hooks] for e.g. pre_clean or post_rebuild, from the git root.
+// succeeded is the command's outcome, and is only known to post_ hooks.
+pub fn run(hook: &str, command: &str, succeeded: Option<bool>) {
+    // sass steps started from a hook don't run hooks of their own, so hooks can't trigger each other in a loop
+    if env::var_os("SASS_HOOK").is_some() {
+        return;
+    }
+    let config: Config = load_config();
+    let Some(lines) = config.hooks.get(hook).filter(|lines| !lines.is_empty()) else {
+        return;
+    };
+    report::step(hook, &format!("Running {} hooks...", hook));
+
+    let gitroot = git_root();
+    let sass = env::current_exe().map(|path| path.display().to_string()).unwrap_or_else(|_| "sass".to_string());
+    let build_status = *BUILD_STATUS.lock().expect("Build status lock poisoned");
+    for line in lines {
+        let mut shell = Command::new("sh");
+        shell
+            .args(["-c", line])
+            .current_dir(&gitroot)
+            .env("SASS_HOOK", hook)
+            .env("SASS_COMMAND", command)
+            .env("SASS", &sass)
+            .env("SASS_PROJECT_ROOT", &gitroot)
+            .env("SASS_SCHEME", &config.scheme)
+            .env("SASS_DELETED_PATHS", report::deleted_paths().join("\n"));
+        if let Some(succeeded) = succeeded {
+            shell.env("SASS_STATUS", if succeeded { "success" } else { "failure" });
+        }
+        if let Some(success) = build_status {
+            shell.env("SASS_BUILD_STATUS", if success { "success" } else { "failure" });
+        }
+        match run_streamed(&mut shell) {
+            Ok((status, _)) if status.success() => (),
+            Ok((status, _)) => panic!("{} hook `{}` failed with {}", hook, line, status),
+            Err(error) => panic!("{} hook `{}` failed: {}", hook, line, error),
+        }
+    }
+}
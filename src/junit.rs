@@ -0,0 +1,126 @@
+use std::fs;
+use std::io;
+use std::path::Path;
+
+use crate::xcresult::{TestOutcome, TestSummary};
+
+// Writes the test results as JUnit XML, one <testsuite> per test suite
+pub fn write(summary: &TestSummary, path: &Path) -> io::Result<()> {
+    let mut suites: Vec<&str> = Vec::new();
+    for case in &summary.cases {
+        if !suites.contains(&case.suite.as_str()) {
+            suites.push(&case.suite);
+        }
+    }
+
+    // Totals count the test cases written below, so they agree with the <testsuite> elements
+    let failures = summary.cases.iter().filter(|case| case.outcome == TestOutcome::Failed).count();
+    let skipped = summary.cases.iter().filter(|case| case.outcome == TestOutcome::Skipped).count();
+    let mut xml = String::from("<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n");
+    xml.push_str(&format!(
+        "<testsuites tests=\"{}\" failures=\"{}\" skipped=\"{}\">\n",
+        summary.cases.len(), failures, skipped
+    ));
+    for suite in suites {
+        let cases: Vec<_> = summary.cases.iter().filter(|case| case.suite == suite).collect();
+        let failures = cases.iter().filter(|case| case.outcome == TestOutcome::Failed).count();
+        let skipped = cases.iter().filter(|case| case.outcome == TestOutcome::Skipped).count();
+        let time: f64 = cases.iter().filter_map(|case| case.duration).sum();
+        xml.push_str(&format!(
+            "  <testsuite name=\"{}\" tests=\"{}\" failures=\"{}\" skipped=\"{}\" time=\"{:.3}\">\n",
+            escape(suite), cases.len(), failures, skipped, time
+        ));
+        for case in cases {
+            xml.push_str(&format!(
+                "    <testcase classname=\"{}\" name=\"{}\" time=\"{:.3}\"",
+                escape(&case.suite), escape(&case.name), case.duration.unwrap_or(0.0)
+            ));
+            match case.outcome {
+                TestOutcome::Passed => xml.push_str("/>\n"),
+                TestOutcome::Skipped => xml.push_str(">\n      <skipped/>\n    </testcase>\n"),
+                TestOutcome::Failed => {
+                    let message = case.messages.first().map(String::as_str).unwrap_or("Test failed");
+                    xml.push_str(&format!(
+                        ">\n      <failure message=\"{}\">{}</failure>\n    </testcase>\n",
+                        escape(message), escape(&case.messages.join("\n"))
+                    ));
+                }
+            }
+        }
+        xml.push_str("  </testsuite>\n");
+    }
+    xml.push_str("</testsuites>\n");
+    fs::write(path, xml)
+}
+
+fn escape(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+        .replace('\'', "&apos;")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::xcresult::TestCase;
+
+    fn case(suite: &str, name: &str, outcome: TestOutcome, messages: &[&str]) -> TestCase {
+        TestCase {
+            suite: suite.to_string(),
+            name: name.to_string(),
+            outcome,
+            duration: Some(0.25),
+            messages: messages.iter().map(|message| message.to_string()).collect(),
+        }
+    }
+
+    fn written(summary: &TestSummary, name: &str) -> String {
+        let path = std::env::temp_dir().join(format!("sass-junit-{}-{}.xml", std::process::id(), name));
+        write(summary, &path).expect("Could not write JUnit XML");
+        let xml = fs::read_to_string(&path).expect("Could not read JUnit XML");
+        let _ = fs::remove_file(&path);
+        xml
+    }
+
+    #[test]
+    fn writes_every_case_grouped_by_suite() {
+        let summary = TestSummary {
+            passed: 2,
+            failed: 1,
+            skipped: 1,
+            cases: vec![
+                case("LoginTests", "testLogin()", TestOutcome::Passed, &[]),
+                case("PriceTests", "testFormat()", TestOutcome::Passed, &[]),
+                case("LoginTests", "testLogout()", TestOutcome::Failed, &["first", "second"]),
+                case("LoginTests", "testBiometrics()", TestOutcome::Skipped, &[]),
+            ],
+        };
+        let xml = written(&summary, "suites");
+        assert!(xml.contains("<testsuites tests=\"4\" failures=\"1\" skipped=\"1\">"), "{}", xml);
+        assert!(xml.contains("<testsuite name=\"LoginTests\" tests=\"3\" failures=\"1\" skipped=\"1\" time=\"0.750\">"), "{}", xml);
+        assert!(xml.contains("<testsuite name=\"PriceTests\" tests=\"1\" failures=\"0\" skipped=\"0\" time=\"0.250\">"), "{}", xml);
+        assert!(xml.contains("<testcase classname=\"LoginTests\" name=\"testLogin()\" time=\"0.250\"/>"), "{}", xml);
+        assert!(xml.contains("<failure message=\"first\">first\nsecond</failure>"), "{}", xml);
+        assert!(xml.contains("name=\"testBiometrics()\" time=\"0.250\">\n      <skipped/>"), "{}", xml);
+    }
+
+    #[test]
+    fn totals_match_the_written_cases() {
+        // Legacy results without their tests object only list failures
+        let summary = TestSummary { passed: 5, failed: 1, skipped: 0, cases: vec![case("LoginTests", "testLogout()", TestOutcome::Failed, &[])] };
+        let xml = written(&summary, "totals");
+        assert!(xml.contains("<testsuites tests=\"1\" failures=\"1\" skipped=\"0\">"), "{}", xml);
+        assert!(xml.contains("<failure message=\"Test failed\"></failure>"), "{}", xml);
+    }
+
+    #[test]
+    fn escapes_xml() {
+        assert_eq!(escape(r#"a < b && "c" > 'd'"#), "a &lt; b &amp;&amp; &quot;c&quot; &gt; &apos;d&apos;");
+        let summary = TestSummary { passed: 0, failed: 1, skipped: 0, cases: vec![case("Suite<T>", "test\"quoted\"()", TestOutcome::Failed, &["(\"a\") & <b>"])] };
+        let xml = written(&summary, "escape");
+        assert!(xml.contains("<testcase classname=\"Suite&lt;T&gt;\" name=\"test&quot;quoted&quot;()\""), "{}", xml);
+        assert!(xml.contains("<failure message=\"(&quot;a&quot;) &amp; &lt;b&gt;\">(&quot;a&quot;) &amp; &lt;b&gt;</failure>"), "{}", xml);
+    }
+}
@@ -0,0 +1,142 @@
+use std::env;
+use std::fs;
+use std::io;
+use std::os::unix::fs::PermissionsExt;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+const KNOWN_LAUNCHERS: [&str; 2] = ["sccache", "ccache"];
+
+// A compiler cache that xcodebuild's clang invocations are routed through
+pub struct Launcher {
+    name: String,
+    path: PathBuf,
+}
+
+// Resolves the `compiler_launcher` setting: unset or "auto" picks the first of sccache and ccache
+// found on PATH, "none" disables caching, anything else is a launcher name or path.
+pub fn resolve(setting: Option<&str>) -> Option<Launcher> {
+    match setting.unwrap_or("auto") {
+        "none" => None,
+        "auto" => KNOWN_LAUNCHERS.iter().find_map(|name| find_on_path(name).map(|path| Launcher { name: name.to_string(), path })),
+        launcher => {
+            let path = if launcher.contains('/') {
+                Some(PathBuf::from(shellexpand::tilde(launcher).into_owned())).filter(|path| path.is_file())
+            } else {
+                find_on_path(launcher)
+            };
+            match path {
+                Some(path) => {
+                    let name = path.file_name().map(|name| name.to_string_lossy().into_owned()).unwrap_or_default();
+                    Some(Launcher { name, path })
+                }
+                None => {
+                    warn!("Warning: compiler launcher {} not found, building without it.", launcher);
+                    None
+                }
+            }
+        }
+    }
+}
+
+pub fn find_on_path(program: &str) -> Option<PathBuf> {
+    let paths = env::var_os("PATH")?;
+    env::split_paths(&paths).map(|dir| dir.join(program)).find(|path| path.is_file())
+}
+
+impl Launcher {
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    // xcodebuild has no launcher setting, so CC/CXX point at wrapper scripts that run the real
+    // clang through the launcher. The linker settings keep linking on plain clang.
+    pub fn build_settings(&self) -> io::Result<Vec<String>> {
+        let wrapper_dir = env::temp_dir().join("sass-launcher").join(&self.name);
+        fs::create_dir_all(&wrapper_dir)?;
+        let clang = xcrun_find("clang").unwrap_or_else(|| "clang".to_string());
+        let clangxx = xcrun_find("clang++").unwrap_or_else(|| "clang++".to_string());
+        let cc = wrapper_dir.join("clang");
+        let cxx = wrapper_dir.join("clang++");
+        self.write_wrapper(&cc, &clang)?;
+        self.write_wrapper(&cxx, &clangxx)?;
+        Ok(vec![
+            format!("CC={}", cc.display()),
+            format!("CXX={}", cxx.display()),
+            format!("LD={}", clang),
+            format!("LDPLUSPLUS={}", clangxx),
+        ])
+    }
+
+    fn write_wrapper(&self, wrapper: &Path, compiler: &str) -> io::Result<()> {
+        let script = format!("#!/bin/sh\nexec \"{}\" \"{}\" \"$@\"\n", self.path.display(), compiler);
+        fs::write(wrapper, script)?;
+        fs::set_permissions(wrapper, fs::Permissions::from_mode(0o755))
+    }
+
+    // Zeroes the statistics so the numbers printed after a build only cover that build
+    pub fn reset_stats(&self) {
+        let _ = Command::new(&self.path).arg("--zero-stats").output();
+    }
+
+    pub fn print_stats(&self) {
+        let output = match Command::new(&self.path).arg("--show-stats").output() {
+            Ok(output) => output,
+            Err(error) => {
+                warn!("Could not read {} stats: {}", self.name, error);
+                return;
+            }
+        };
+        say!("{} stats:", self.name);
+        let stats = String::from_utf8_lossy(&output.stdout);
+        for line in stats.lines().filter(|line| {
+            let line = line.to_lowercase();
+            line.contains("hit") || line.contains("miss") || line.contains("requests")
+        }) {
+            say!("  {}", line.trim());
+        }
+    }
+}
+
+fn xcrun_find(tool: &str) -> Option<String> {
+    let output = Command::new("xcrun").args(["--find", tool]).output().ok()?;
+    if !output.status.success() {
+        return None;
+    }
+    Some(String::from_utf8_lossy(&output.stdout).trim().to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn resolves_none_and_paths() {
+        assert!(resolve(Some("none")).is_none());
+        assert!(resolve(Some("/nonexistent/sccache")).is_none());
+        let path = env::temp_dir().join(format!("sass-launcher-{}-mycache", std::process::id()));
+        fs::write(&path, "").unwrap();
+        let launcher = resolve(Some(&path.display().to_string())).expect("Launcher path was not resolved");
+        assert_eq!(launcher.name(), path.file_name().unwrap().to_string_lossy());
+        assert_eq!(launcher.path, path);
+    }
+
+    #[test]
+    fn routes_compilers_through_wrappers_and_links_with_clang() {
+        let launcher = Launcher { name: format!("sass-test-{}", std::process::id()), path: PathBuf::from("/opt/cache/bin/cache") };
+        let settings = launcher.build_settings().expect("Could not write wrappers");
+        let wrapper_dir = env::temp_dir().join("sass-launcher").join(launcher.name());
+        let clang = xcrun_find("clang").unwrap_or_else(|| "clang".to_string());
+        let clangxx = xcrun_find("clang++").unwrap_or_else(|| "clang++".to_string());
+        assert_eq!(settings, [
+            format!("CC={}", wrapper_dir.join("clang").display()),
+            format!("CXX={}", wrapper_dir.join("clang++").display()),
+            format!("LD={}", clang),
+            format!("LDPLUSPLUS={}", clangxx),
+        ]);
+        let script = fs::read_to_string(wrapper_dir.join("clang++")).unwrap();
+        assert_eq!(script, format!("#!/bin/sh\nexec \"/opt/cache/bin/cache\" \"{}\" \"$@\"\n", clangxx));
+        let mode = fs::metadata(wrapper_dir.join("clang")).unwrap().permissions().mode();
+        assert_eq!(mode & 0o111, 0o111);
+    }
+}
@@ -0,0 +1,262 @@
+use std::fmt;
+use std::fs;
+use std::io;
+use std::path::Path;
+
+use regex::Regex;
+
+use crate::xcresult::Severity;
+
+#[derive(Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Category {
+    Compiler,
+    Linker,
+    MacroPlugin,
+    CodeSigning,
+    Build,
+}
+
+impl fmt::Display for Category {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let name = match self {
+            Category::Compiler => "compiler",
+            Category::Linker => "linker",
+            Category::MacroPlugin => "macro/plugin",
+            Category::CodeSigning => "code signing",
+            Category::Build => "build",
+        };
+        write!(f, "{}", name)
+    }
+}
+
+#[derive(PartialEq)]
+pub struct Diagnostic {
+    pub category: Category,
+    pub severity: Severity,
+    pub file: Option<String>,
+    pub line: Option<u32>,
+    pub column: Option<u32>,
+    pub message: String,
+}
+
+// The de-duplicated errors and warnings found in an xcodebuild log, in the order they first appeared
+pub struct LogReport {
+    pub diagnostics: Vec<Diagnostic>,
+}
+
+const SIGNING_MARKERS: [&str; 7] = ["Code Signing Error", "Signing for", "provisioning profile", "No profiles for", "signing certificate", "errSec", "CodeSign"];
+
+pub fn parse_file(path: &Path) -> io::Result<LogReport> {
+    let log = fs::read_to_string(path)?;
+    Ok(parse(&log))
+}
+
+pub fn parse(log: &str) -> LogReport {
+    let located = Regex::new(r"^(?<file>/[^:]+):(?<line>\d+):(?:(?<column>\d+):)? (?<severity>fatal error|error|warning): (?<message>.+)$").expect("Located diagnostic regex failed to parse");
+    let unlocated = Regex::new(r"^(?:(?<tool>[\w.+-]+): )?(?<severity>fatal error|error|warning): (?<message>.+)$").expect("Diagnostic regex failed to parse");
+    let signing = Regex::new(r"^Code ?Signing Error: (?<message>.+)$").expect("Signing regex failed to parse");
+    let undefined_symbol = Regex::new(r#"^\s+"?(?<symbol>[^",]+)"?, referenced from:"#).expect("Undefined symbol regex failed to parse");
+
+    let mut report = LogReport { diagnostics: Vec::new() };
+    let mut in_undefined_symbols = false;
+    for line in log.lines() {
+        let line = line.trim_end();
+        if in_undefined_symbols {
+            if let Some(captures) = undefined_symbol.captures(line) {
+                report.push(Diagnostic {
+                    category: Category::Linker,
+                    severity: Severity::Error,
+                    file: None,
+                    line: None,
+                    column: None,
+                    message: format!("Undefined symbol: {}", &captures["symbol"]),
+                });
+                continue;
+            }
+            // Symbol entries are followed by indented "referenced from" lines, anything else ends the block
+            if line.starts_with(char::is_whitespace) {
+                continue;
+            }
+            in_undefined_symbols = false;
+        }
+        if line.starts_with("Undefined symbols for architecture") || line == "ld: Undefined symbols:" {
+            in_undefined_symbols = true;
+            continue;
+        }
+
+        if let Some(captures) = located.captures(line) {
+            let message = captures["message"].to_string();
+            let category = if mentions_macro_or_plugin(&message) { Category::MacroPlugin } else { Category::Compiler };
+            report.push(Diagnostic {
+                category,
+                severity: severity(&captures["severity"]),
+                file: Some(captures["file"].to_string()),
+                line: captures["line"].parse().ok(),
+                column: captures.name("column").and_then(|column| column.as_str().parse().ok()),
+                message,
+            });
+        } else if let Some(captures) = signing.captures(line) {
+            report.push(Diagnostic {
+                category: Category::CodeSigning,
+                severity: Severity::Error,
+                file: None,
+                line: None,
+                column: None,
+                message: captures["message"].to_string(),
+            });
+        } else if let Some(captures) = unlocated.captures(line) {
+            let message = captures["message"].to_string();
+            let tool = captures.name("tool").map(|tool| tool.as_str()).unwrap_or_default();
+            let category = if tool == "ld" || message.starts_with("linker command failed") {
+                Category::Linker
+            } else if SIGNING_MARKERS.iter().any(|marker| message.contains(marker)) {
+                Category::CodeSigning
+            } else if mentions_macro_or_plugin(&message) {
+                Category::MacroPlugin
+            } else {
+                Category::Build
+            };
+            report.push(Diagnostic {
+                category,
+                severity: severity(&captures["severity"]),
+                file: None,
+                line: None,
+                column: None,
+                message,
+            });
+        }
+    }
+    report
+}
+
+// clang reports missing headers as fatal errors
+fn severity(name: &str) -> Severity {
+    if name == "warning" { Severity::Warning } else { Severity::Error }
+}
+
+fn mentions_macro_or_plugin(message: &str) -> bool {
+    let message = message.to_lowercase();
+    message.contains("macro") || message.contains("plugin")
+}
+
+impl LogReport {
+    // xcodebuild repeats diagnostics, e.g. once per architecture and again in the failure summary
+    fn push(&mut self, diagnostic: Diagnostic) {
+        if !self.diagnostics.contains(&diagnostic) {
+            self.diagnostics.push(diagnostic);
+        }
+    }
+
+    pub fn count(&self, severity: Severity) -> usize {
+        self.diagnostics.iter().filter(|diagnostic| diagnostic.severity == severity).count()
+    }
+
+    // Diagnostics grouped by file, files in order of first appearance and diagnostics without a file last
+    pub fn by_file(&self) -> Vec<(Option<&str>, Vec<&Diagnostic>)> {
+        let mut groups: Vec<(Option<&str>, Vec<&Diagnostic>)> = Vec::new();
+        for diagnostic in &self.diagnostics {
+            let file = diagnostic.file.as_deref();
+            match groups.iter_mut().find(|(group_file, _)| *group_file == file) {
+                Some((_, diagnostics)) => diagnostics.push(diagnostic),
+                None => groups.push((file, vec![diagnostic])),
+            }
+        }
+        groups.sort_by_key(|(file, _)| file.is_none());
+        groups
+    }
+
+    pub fn print(&self) {
+        if self.diagnostics.is_empty() {
+            say!("No errors or warnings found in the build log.");
+            return;
+        }
+        for (file, diagnostics) in self.by_file() {
+            say!("{}", file.unwrap_or("(no file)"));
+            for diagnostic in diagnostics {
+                let position = match (diagnostic.line, diagnostic.column) {
+                    (Some(line), Some(column)) => format!("{}:{} ", line, column),
+                    (Some(line), None) => format!("{} ", line),
+                    _ => String::new(),
+                };
+                say!("  {}{} [{}]: {}", position, diagnostic.severity, diagnostic.category, diagnostic.message);
+            }
+        }
+        let mut categories: Vec<Category> = self.diagnostics.iter().map(|diagnostic| diagnostic.category).collect();
+        categories.sort();
+        categories.dedup();
+        let breakdown: Vec<String> = categories.iter().map(|category| {
+            let count = self.diagnostics.iter().filter(|diagnostic| diagnostic.category == *category).count();
+            format!("{} {}", count, category)
+        }).collect();
+        say!("{} errors, {} warnings ({})", self.count(Severity::Error), self.count(Severity::Warning), breakdown.join(", "));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::fixture;
+
+    fn sample() -> LogReport {
+        parse_file(&fixture("logs/build.log")).expect("Could not read sample log")
+    }
+
+    fn messages(report: &LogReport, category: Category) -> Vec<&str> {
+        report.diagnostics.iter().filter(|diagnostic| diagnostic.category == category).map(|diagnostic| diagnostic.message.as_str()).collect()
+    }
+
+    #[test]
+    fn reads_fatal_errors() {
+        let report = sample();
+        let fatal = report.diagnostics.iter().find(|diagnostic| diagnostic.message.contains("file not found")).expect("Missing header was dropped");
+        assert!(fatal.severity == Severity::Error);
+        assert_eq!(fatal.file.as_deref(), Some("/Users/dev/App/Sources/Legacy.m"));
+        assert_eq!((fatal.line, fatal.column), (Some(3), Some(9)));
+    }
+
+    #[test]
+    fn drops_repeated_diagnostics() {
+        let report = sample();
+        let repeated = report.diagnostics.iter().filter(|diagnostic| diagnostic.message == "cannot find 'viewModel' in scope").count();
+        assert_eq!(repeated, 1);
+        assert_eq!((report.count(Severity::Error), report.count(Severity::Warning)), (8, 2));
+    }
+
+    #[test]
+    fn reads_the_undefined_symbols_block() {
+        let report = sample();
+        assert_eq!(messages(&report, Category::Linker), [
+            "Undefined symbol: _OBJC_CLASS_$_FIRApp",
+            "Undefined symbol: _swift_FORCE_LOAD_$_swiftCompatibility56",
+            "linker command failed with exit code 1 (use -v to see invocation)",
+        ]);
+    }
+
+    #[test]
+    fn reads_signing_errors() {
+        let report = sample();
+        let signing = messages(&report, Category::CodeSigning);
+        assert_eq!(signing.len(), 2);
+        assert!(signing[0].starts_with("No profiles for 'com.example.app' were found"));
+        assert!(signing[1].starts_with("Signing for \"App\" requires a development team"));
+    }
+
+    #[test]
+    fn categorises_macro_errors() {
+        let report = sample();
+        assert_eq!(messages(&report, Category::MacroPlugin), ["external macro implementation type 'ModelMacros.ObservableMacro' could not be found for macro 'Observable()'"]);
+    }
+
+    #[test]
+    fn groups_by_file_with_unlocated_diagnostics_last() {
+        let report = sample();
+        let files: Vec<Option<&str>> = report.by_file().into_iter().map(|(file, _)| file).collect();
+        assert_eq!(files, [
+            Some("/Users/dev/App/Sources/Legacy.m"),
+            Some("/Users/dev/App/Sources/ContentView.swift"),
+            Some("/Users/dev/App/Sources/Counter.swift"),
+            Some("/Users/dev/App/Sources/Model.swift"),
+            None,
+        ]);
+    }
+}
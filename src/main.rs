@@ -2,147 +2,446 @@ use cli_builder_macros::cli_builder;
 use toml_configurator::configurator_macros::config_builder;
 use toml_configurator::freezable_trait;
 use toml_configurator::get_config;
+use std::collections::HashMap;
 use std::fs;
 use std::io;
+use std::io::{BufRead, BufReader, Read};
+use std::os::unix::process::CommandExt;
 use std::path::Path;
 use std::path::PathBuf;
 use std::env;
-use std::process::Command;
+use std::process::{self, Command, ExitStatus, Stdio};
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
 use std::{thread, time};
 
 use copy_dir::copy_dir;
 use file_lock::{FileLock, FileOptions};
 use regex::Regex;
+use serde::{Deserialize, Serialize};
+use serde_json::json;
 use walkdir::WalkDir;
 
+use cli::Runtime;
+use report::ReportedCommand;
+
+#[macro_use]
+mod report;
+mod build_server;
+mod deps_scripts;
+mod doctor;
+mod hooks;
+mod junit;
+mod launcher;
+mod log_parser;
+mod pods;
+mod retry;
+mod simulators;
+mod spm_cache;
+#[cfg(test)]
+mod test_support;
+mod watch;
+mod xcresult;
+
 config_builder! {
     post_install_script_location: Option<String> = None,
     project_derived_data_recursive_root: Option<String> = None,
+    derived_data_location: Option<String> = None,
+    compiler_launcher: Option<String> = None,
+    offline: bool = false,
+    check_pods_before_rebuild: bool = false,
     scheme: String = "".to_string(),
     workspace_name: String = "".to_string(),
+    destination: String = "generic/platform=iOS Simulator".to_string(),
+    test_destination: Option<String> = None,
+    test_plan: Option<String> = None,
+    default_profile: Option<String> = None,
+    xcodebuild_args: Vec<String> = Vec::new(),
+    simulator_runtimes_keep: Vec<String> = Vec::new(),
+    simulator_runtime_majors: Option<usize> = None,
+    default_simulator: Option<String> = None,
+    build_settings: HashMap<String, String> = HashMap::new(),
+    profiles: HashMap<String, Profile> = HashMap::new(),
+    retry: HashMap<String, retry::RetryOverrides> = HashMap::new(),
+    hooks: HashMap<String, Vec<String>> = HashMap::new(),
+    deps_scripts: Vec<deps_scripts::DepsScript> = Vec::new(),
+    simulators: Vec<simulators::SimulatorDevice> = Vec::new(),
+}
+
+// A [profiles.<name>] section, overriding the top level settings when selected
+#[derive(Serialize, Deserialize, Clone, Debug, Default, PartialEq)]
+#[serde(default)]
+struct Profile {
+    scheme: Option<String>,
+    workspace_name: Option<String>,
+    destination: Option<String>,
+    test_destination: Option<String>,
+    test_plan: Option<String>,
+    xcodebuild_args: Vec<String>,
+    build_settings: HashMap<String, String>,
+}
+
+impl Profile {
+    fn apply(self, config: &mut Config) {
+        if let Some(scheme) = self.scheme {
+            config.scheme = scheme;
+        }
+        if let Some(workspace_name) = self.workspace_name {
+            config.workspace_name = workspace_name;
+        }
+        if let Some(destination) = self.destination {
+            config.destination = destination;
+        }
+        if self.test_destination.is_some() {
+            config.test_destination = self.test_destination;
+        }
+        if self.test_plan.is_some() {
+            config.test_plan = self.test_plan;
+        }
+        config.xcodebuild_args.extend(self.xcodebuild_args);
+        config.build_settings.extend(self.build_settings);
+    }
+}
+
+// Loads the config with the selected profile (--profile, then default_profile) applied
+fn load_config() -> Config {
+    let mut config: Config = get_config("sass".to_string());
+    if let Some(name) = arg_value("profile").or(config.default_profile.clone()) {
+        match config.profiles.get(&name).cloned() {
+            Some(profile) => profile.apply(&mut config),
+            None => panic!("Profile {} not found in config", name),
+        }
+    }
+    config
+}
+
+// cli_builder!'s generated main only takes a single flag, so it lives in its own module and the main below
+// dispatches on the command's flag instead, leaving the rest of the arguments to the command
+#[allow(dead_code, clippy::cmp_owned, clippy::needless_return)]
+mod cli {
+    use super::*;
+
+    cli_builder! {
+        [
+            CLICommand {
+                short_flag: "ci",
+                long_flag: "clean-intermediates",
+                command: quick_clean_command,
+                description: "cleans build intermediates that can cause problems"
+            },
+            CLICommand {
+                short_flag: "c",
+                long_flag: "clean",
+                command: clean_command,
+                description: "cleans derived data and packages and rebuilds project"
+            },
+            CLICommand {
+                short_flag: "f",
+                long_flag: "full-clean",
+                command: full_clean_command,
+                description: "rebuilds project, force purging everything it can (slow)"
+            },
+            CLICommand {
+                short_flag: "fc",
+                long_flag: "full-clean-compat",
+                command: full_clean_command,
+                description: "compatibility alias for -f"
+            },
+            CLICommand {
+                short_flag: "r",
+                long_flag: "rebuild",
+                command: rebuild_command,
+                description: "rebuilds the project via xcodebuild on your configured workspace and scheme, then rebuilds the build server. --schemes A,B or --all-schemes builds several, --jobs N in parallel"
+            },
+            CLICommand {
+                short_flag: "te",
+                long_flag: "test",
+                command: test_command,
+                description: "runs the configured scheme's tests and writes JUnit XML (--without-building, --test-plan NAME, --junit PATH)"
+            },
+            CLICommand {
+                short_flag: "bs",
+                long_flag: "build-server",
+                command: rebuild_build_server_command,
+                description: "reconstructs buildServer.json via your configured workspace and scheme"
+            },
+            CLICommand {
+                short_flag: "d",
+                long_flag: "deps-script",
+                command: run_deps_script_command,
+                description: "runs a custom script configurable via the config.toml (run -i, edit ~/.config/sass/config.toml)"
+            },
+            CLICommand {
+                short_flag: "t",
+                long_flag: "update-templates",
+                command: update_templates_command,
+                description: "copies the contents of ~/.config/sass/templates/ to your xcode templates dir under a 'sass' subfolder, overwriting previous contents"
+            },
+            CLICommand {
+                short_flag: "w",
+                long_flag: "wipe-derived",
+                command: generic_wipe_derived_command,
+                description: "purges derived data"
+            },
+            CLICommand {
+                short_flag: "p",
+                long_flag: "reset-packages",
+                command: reset_packages_command,
+                description: "reinstalls spm packages in non-build subdirectories"
+            },
+            CLICommand {
+                short_flag: "cp",
+                long_flag: "clean-packages",
+                command: clean_packages_command,
+                description: "uses swiftcli tools to clean your packages"
+            },
+            CLICommand {
+                short_flag: "cP",
+                long_flag: "clean-pods",
+                command: wipe_pods_command,
+                description: "uses swiftcli tools to clean your pods"
+            },
+            CLICommand {
+                short_flag: "ip",
+                long_flag: "install-packages",
+                command: install_packages_command,
+                description: "uses swiftcli tools to install SPM packages at the versions pinned in Package.resolved"
+            },
+            CLICommand {
+                short_flag: "pu",
+                long_flag: "packages-update",
+                command: update_packages_command,
+                description: "updates SPM dependencies past Package.resolved, all of them or only the named ones"
+            },
+            CLICommand {
+                short_flag: "sc",
+                long_flag: "spm-cache",
+                command: spm_cache_command,
+                description: "manages the global SwiftPM cache: list, remove <name...>, prune [--dry-run] entries no Package.resolved in the project uses"
+            },
+            CLICommand {
+                short_flag: "po",
+                long_flag: "pods",
+                command: pods_command,
+                description: "clean <PodName...>, clean --from-lockfile or [check] [--fix]: targeted pod cleanup and Podfile.lock/Manifest.lock sync check"
+            },
+            CLICommand {
+                short_flag: "iP",
+                long_flag: "install-pods",
+                command: install_pods_command,
+                description: "runs pod install (via bundler if detected)"
+            },
+            CLICommand {
+                short_flag: "ns",
+                long_flag: "nuke-simulators",
+                command: nuke_simulators_command,
+                description: "Uninstalls (shutdown) simulators from previous iOS versions and purges symbolication caches"
+            },
+            CLICommand {
+                short_flag: "si",
+                long_flag: "sim",
+                command: sim_command,
+                description: "runtimes [list|prune] (the default is list): lists installed simulator runtimes with their size, prune deletes those older than --keep-majors N or not in simulator_runtimes_keep (--dry-run). sync: creates and renames devices to match [[simulators]] (--delete-undeclared, --dry-run). erase [--all], boot, push <bundle id> <payload>, openurl <url>, privacy <grant|revoke|reset> <service> [bundle id] on the default simulator (--device)"
+            },
+            CLICommand {
+                short_flag: "wp",
+                long_flag: "wipe-project-derived",
+                command: wipe_project_derived_data_command,
+                description: "Deletes all subfolders named DerivedData-* recursively in the configured parent directory (project_derived_data_recursive_root)."
+            },
+            CLICommand {
+                short_flag: "dc",
+                long_flag: "derived-clean",
+                command: derived_clean_command,
+                description: "removes parts of the configured project's derived data, e.g. --parts modulecache,index,packages,products,logs,headers (add --global for every project)"
+            },
+            CLICommand {
+                short_flag: "wa",
+                long_flag: "watch",
+                command: watch_command,
+                description: "watches package, pod, project and workspace manifests and reinstalls dependencies and regenerates buildServer.json when they change"
+            },
+            CLICommand {
+                short_flag: "dr",
+                long_flag: "doctor",
+                command: doctor_command,
+                description: "checks the external tools sass uses and the configured workspace, scheme and deps script (--json for machine-readable output)"
+            },
+            CLICommand {
+                short_flag: "pl",
+                long_flag: "parse-log",
+                command: parse_log_command,
+                description: "groups the errors and warnings in a saved xcodebuild log by file"
+            },
+            CLICommand {
+                short_flag: "rs",
+                long_flag: "results-show",
+                command: results_show_command,
+                description: "summarises a result bundle, by default the .bundle written by the last rebuild (--issues N to list more issues)"
+            },
+        ]
+    }
+}
+
+fn main() {
+    let runtime = Runtime::new();
+    let args: Vec<String> = sass_args().collect();
+    if args.is_empty() {
+        if let Some(command) = runtime.default_command {
+            command();
+        }
+        return;
+    }
+    // The first argument naming a command picks it, so options may come before or after the command's flag
+    let command = args.iter().find_map(|arg| {
+        runtime.commands.iter().find(|command| *arg == format!("--{}", command.long_flag) || *arg == format!("-{}", command.short_flag))
+    });
+    match command {
+        Some(command) if command.long_flag == "completions" => println!("{}", runtime.gen_completions_2()),
+        Some(command) => (command.command)(),
+        None => {
+            println!("Unknown command {}", args[0]);
+            println!("{}", runtime.gen_help());
+            process::exit(1);
+        }
+    }
+}
+
+// Every CLI entry point goes through report::run_command, which reports the command's final status.
+// The name after `as` picks the command's pre_/post_ hooks.
+macro_rules! cli_commands {
+    ($($entry:ident => $command:ident as $hook:ident),* $(,)?) => {
+        $(
+            fn $entry() {
+                report::run_command(stringify!($command), stringify!($hook), $command);
+            }
+        )*
+    };
+}
+
+cli_commands! {
+    quick_clean_command => quick_clean as clean_intermediates,
+    clean_command => clean as clean,
+    full_clean_command => full_clean as full_clean,
+    rebuild_command => rebuild as rebuild,
+    test_command => test as test,
+    rebuild_build_server_command => rebuild_build_server as build_server,
+    run_deps_script_command => run_deps_script as deps_script,
+    update_templates_command => update_templates as update_templates,
+    generic_wipe_derived_command => generic_wipe_derived as wipe_derived,
+    reset_packages_command => reset_packages as reset_packages,
+    clean_packages_command => clean_packages as clean_packages,
+    wipe_pods_command => wipe_pods as clean_pods,
+    install_packages_command => install_packages as install_packages,
+    update_packages_command => update_packages as packages_update,
+    spm_cache_command => spm_cache as spm_cache,
+    pods_command => pods as pods,
+    install_pods_command => install_pods as install_pods,
+    nuke_simulators_command => nuke_simulators as nuke_simulators,
+    sim_command => sim as sim,
+    wipe_project_derived_data_command => wipe_project_derived_data as wipe_project_derived,
+    derived_clean_command => derived_clean as derived_clean,
+    watch_command => watch as watch,
+    doctor_command => doctor as doctor,
+    parse_log_command => parse_log as parse_log,
+    results_show_command => results_show as results_show,
+}
+
+fn watch() {
+    watch::watch();
 }
 
-cli_builder! {
-    [
-        CLICommand {
-            short_flag: "ci",
-            long_flag: "clean-intermediates",
-            command: quick_clean,
-            description: "cleans build intermediates that can cause problems"
-        },
-        CLICommand {
-            short_flag: "c",
-            long_flag: "clean",
-            command: clean,
-            description: "cleans derived data and packages and rebuilds project"
-        },
-        CLICommand {
-            short_flag: "f",
-            long_flag: "full-clean",
-            command: full_clean,
-            description: "rebuilds project, force purging everything it can (slow)"
-        },
-        CLICommand {
-            short_flag: "fc",
-            long_flag: "full-clean-compat",
-            command: full_clean,
-            description: "compatibility alias for -f"
-        },
-        CLICommand {
-            short_flag: "r",
-            long_flag: "rebuild",
-            command: rebuild,
-            description: "rebuilds the project via xcodebuild on your configured workspace and scheme, then rebuilds the build server"
-        },
-        CLICommand {
-            short_flag: "bs",
-            long_flag: "build-server",
-            command: rebuild_build_server,
-            description: "reconstructs buildServer.json via your configured workspace and scheme"
-        },
-        CLICommand {
-            short_flag: "d",
-            long_flag: "deps-script",
-            command: run_deps_script,
-            description: "runs a custom script configurable via the config.toml (run -i, edit ~/.config/sass/config.toml)"
-        },
-        CLICommand {
-            short_flag: "t",
-            long_flag: "update-templates",
-            command: update_templates,
-            description: "copies the contents of ~/.config/sass/templates/ to your xcode templates dir under a 'sass' subfolder, overwriting previous contents"
-        },
-        CLICommand {
-            short_flag: "w",
-            long_flag: "wipe-derived",
-            command: generic_wipe_derived,
-            description: "purges derived data"
-        },
-        CLICommand {
-            short_flag: "p",
-            long_flag: "reset-packages",
-            command: reset_packages,
-            description: "reinstalls spm packages in non-build subdirectories"
-        },
-        CLICommand {
-            short_flag: "cp",
-            long_flag: "clean-packages",
-            command: clean_packages,
-            description: "uses swiftcli tools to clean your packages"
-        },
-        CLICommand {
-            short_flag: "cP",
-            long_flag: "clean-pods",
-            command: wipe_pods,
-            description: "uses swiftcli tools to clean your pods"
-        },
-        CLICommand {
-            short_flag: "ip",
-            long_flag: "install-packages",
-            command: install_packages,
-            description: "uses swiftcli tools to install SPM packages"
-        },
-        CLICommand {
-            short_flag: "iP",
-            long_flag: "install-pods",
-            command: install_pods,
-            description: "runs pod install (via bundler if detected)"
-        },
-        CLICommand {
-            short_flag: "ns",
-            long_flag: "nuke-simulators",
-            command: nuke_simulators,
-            description: "Uninstalls (shutdown) simulators from previous iOS versions and purges symbolication caches"
-        },
-        CLICommand {
-            short_flag: "wp",
-            long_flag: "wipe-project-derived",
-            command: wipe_project_derived_data,
-            description: "Deletes all subfolders named DerivedData-* recursively in the configured parent directory (project_derived_data_recursive_root)."
-        },
-    ]
+fn doctor() {
+    doctor::doctor();
+}
+
+fn spm_cache() {
+    spm_cache::spm_cache();
+}
+
+fn pods() {
+    pods::pods();
+}
+
+fn sim() {
+    simulators::sim();
+}
+
+fn results_show() {
+    let path = match operands("results-show", "rs").into_iter().next() {
+        Some(path) => PathBuf::from(path),
+        None => Path::new(&git_root()).join(".bundle"),
+    };
+    match xcresult::load(&path) {
+        Ok(digest) => digest.print(issue_limit()),
+        Err(error) => warn!("Error: {}", error),
+    }
+}
+
+fn parse_log() {
+    let paths = operands("parse-log", "pl");
+    if paths.is_empty() {
+        say!("Pass the log file to parse, e.g. sass --parse-log build.log");
+    }
+    for path in paths {
+        match log_parser::parse_file(Path::new(&path)) {
+            Ok(report) => report.print(),
+            Err(error) => warn!("Error reading {}: {}", path, error),
+        }
+    }
+}
+
+fn issue_limit() -> usize {
+    arg_value("issues").and_then(|limit| limit.parse().ok()).unwrap_or(xcresult::DEFAULT_ISSUE_LIMIT)
 }
 
 fn run_deps_script() {
-    install_deps_script().expect("Error running deps script");
+    if !install_deps_script() {
+        panic!("No deps script configured, set post_install_script_location or add [[deps_scripts]] to the config");
+    }
 }
 
 fn reset_packages() {
     clean_packages();
+    clean_workspace_packages();
     install_packages();
 }
 
 fn quick_clean() {
-    wipe_derived_data(true);
+    wipe_derived_data(&[DerivedDataPart::PrecompiledHeaders], None);
 }
 
 fn generic_wipe_derived() {
-    wipe_derived_data(false);
+    wipe_derived_data(&[], None);
+}
+
+fn derived_clean() {
+    let parts_arg = arg_value("parts").unwrap_or_else(|| "headers".to_string());
+    let mut parts: Vec<DerivedDataPart> = Vec::new();
+    for name in parts_arg.split(',').map(|part| part.trim()).filter(|part| !part.is_empty()) {
+        match DerivedDataPart::from_name(name) {
+            Some(part) => parts.push(part),
+            None => {
+                warn!("Unknown derived data part '{}'. Expected one of: {}", name, DerivedDataPart::NAMES.join(", "));
+                return;
+            }
+        }
+    }
+    if has_flag("global") {
+        wipe_derived_data(&parts, None);
+    } else {
+        let config: Config = load_config();
+        if config.workspace_name.is_empty() {
+            say!("No workspace_name configured, pass --global to clean every project.");
+            return;
+        }
+        wipe_derived_data(&parts, Some(&config.workspace_name));
+    }
 }
 
 fn clean() {
     clean_packages();
-    wipe_derived_data(false);
+    wipe_derived_data(&[], None);
     install_packages();
     rebuild();
     rebuild_build_server();
@@ -151,7 +450,7 @@ fn clean() {
 fn full_clean() {
     clean_packages();
     wipe_pod_cache_hard();
-    wipe_derived_data(false);
+    wipe_derived_data(&[], None);
     install_deps_script();
     install_packages();
     let dur = time::Duration::from_millis(999);
@@ -162,56 +461,380 @@ fn full_clean() {
 }
 
 fn rebuild() {
-    println!("Building...");
-    let config: Config = get_config("sass".to_string());
+    report::step("rebuild", "Building...");
+    let config: Config = load_config();
     let gitroot = git_root();
     let pods_dir = gitroot.clone() + "/.bundle/";
-    match fs::remove_dir_all(pods_dir) {
-        Ok(_result) => (),
-        Err(error) => println!("Error: {}", error),
+    retry::remove_dir_all(Path::new(&pods_dir));
+    if config.check_pods_before_rebuild && Path::new(&gitroot).join("Podfile.lock").exists() {
+        pods::check(true);
+    }
+    let mut launcher_settings: Vec<String> = Vec::new();
+    let launcher = launcher::resolve(config.compiler_launcher.as_deref());
+    if let Some(launcher) = &launcher {
+        match launcher.build_settings() {
+            Ok(settings) => {
+                say!("Using {} as compiler launcher.", launcher.name());
+                launcher.reset_stats();
+                launcher_settings = settings;
+            }
+            Err(error) => warn!("Error setting up {}: {}", launcher.name(), error),
+        }
+    }
+
+    let schemes = selected_schemes(&config, &gitroot);
+    let results = if let [scheme] = schemes.as_slice() {
+        vec![build_scheme(&config, &gitroot, scheme, ".bundle", None, &launcher_settings)]
+    } else {
+        let jobs = arg_value("jobs").and_then(|jobs| jobs.parse::<usize>().ok()).unwrap_or(1).max(1);
+        if let Err(error) = fs::create_dir_all(Path::new(&gitroot).join(".bundle")) {
+            warn!("Error: {}", error);
+        }
+        let results = build_schemes(&config, &gitroot, &schemes, jobs, &launcher_settings);
+        print_build_summary(&results);
+        results
+    };
+    if let Some(launcher) = &launcher {
+        launcher.print_stats();
+    }
+    let failed: Vec<&str> = results.iter().filter(|result| !result.success).map(|result| result.scheme.as_str()).collect();
+    if !failed.is_empty() {
+        panic!("Build failed for {}", failed.join(", "));
     }
-    let workspace = config.workspace_name;
-    let scheme = config.scheme;
-    let output = Command::new("xcodebuild")
-        .args(["-workspace", format!("{}.xcworkspace", workspace).as_str(), "-scheme", scheme.as_str(), "-destination", r"generic/platform=iOS Simulator", "-resultBundlePath", ".bundle", "OTHER_CFLAGS=\"-DCMAKE_C_COMPILER_LAUNCHER=$(which sccache) -DCMAKE_CXX_COMPILER_LAUNCHER=$(which sccache)\""])
-        .current_dir(gitroot)
-        .output()
-        .expect("failed to execute process");
-    println!("{}", String::from_utf8(output.stdout).expect("Error executing build"));
     rebuild_build_server();
 }
 
+struct BuildResult {
+    scheme: String,
+    success: bool,
+    duration: time::Duration,
+}
+
+// The configured scheme, unless --schemes A,B or --all-schemes is passed
+fn selected_schemes(config: &Config, gitroot: &str) -> Vec<String> {
+    if has_flag("all-schemes") {
+        let schemes = shared_schemes(Path::new(gitroot));
+        if schemes.is_empty() {
+            panic!("No shared schemes found in {}", gitroot);
+        }
+        return schemes;
+    }
+    match arg_value("schemes") {
+        Some(schemes) => {
+            let schemes: Vec<String> = schemes.split(',').map(|scheme| scheme.trim()).filter(|scheme| !scheme.is_empty()).map(|scheme| scheme.to_string()).collect();
+            if schemes.is_empty() {
+                panic!("--schemes needs at least one scheme name");
+            }
+            schemes
+        }
+        None => vec![config.scheme.clone()],
+    }
+}
+
+// Shared schemes are the .xcscheme files under xcshareddata/xcschemes, ignoring those generated for pods
+fn shared_schemes(root: &Path) -> Vec<String> {
+    let mut schemes: Vec<String> = Vec::new();
+    for path in project_files(root) {
+        let is_shared_scheme = path.extension().is_some_and(|extension| extension == "xcscheme")
+            && path.parent().is_some_and(|parent| parent.ends_with("xcshareddata/xcschemes"));
+        if let Some(name) = path.file_stem().filter(|_| is_shared_scheme) {
+            let name = name.to_string_lossy().into_owned();
+            if !schemes.contains(&name) {
+                schemes.push(name);
+            }
+        }
+    }
+    schemes.sort();
+    schemes
+}
+
+fn build_scheme(config: &Config, gitroot: &str, scheme: &str, result_bundle: &str, derived_data: Option<&Path>, launcher_settings: &[String]) -> BuildResult {
+    let started = time::Instant::now();
+    let mut args: Vec<String> = ["-workspace", format!("{}.xcworkspace", config.workspace_name).as_str(), "-scheme", scheme, "-destination", config.destination.as_str(), "-resultBundlePath", result_bundle]
+        .iter()
+        .map(|arg| arg.to_string())
+        .collect();
+    if let Some(derived_data) = derived_data {
+        args.push("-derivedDataPath".to_string());
+        args.push(derived_data.display().to_string());
+    }
+    args.extend(launcher_settings.iter().cloned());
+    let (status, log) = run_streamed(xcodebuild(&config.xcodebuild_args, &config.build_settings)
+        .args(args)
+        .current_dir(gitroot))
+        .expect("failed to execute process");
+    say!("Scheme {}:", scheme);
+    match xcresult::load(&Path::new(gitroot).join(result_bundle)) {
+        Ok(digest) => digest.print(issue_limit()),
+        Err(error) => warn!("Could not summarise {}: {}", result_bundle, error),
+    }
+    let log_report = log_parser::parse(&log);
+    log_report.print();
+    report::result("build", json!({
+        "scheme": scheme,
+        "success": status.success(),
+        "errors": log_report.count(xcresult::Severity::Error),
+        "warnings": log_report.count(xcresult::Severity::Warning),
+        "duration_ms": started.elapsed().as_millis() as u64,
+    }));
+    hooks::record_build(status.success());
+    BuildResult {
+        scheme: scheme.to_string(),
+        success: status.success(),
+        duration: started.elapsed(),
+    }
+}
+
+// Builds each scheme into its own result bundle under .bundle/, running up to `jobs` builds at once
+fn build_schemes(config: &Config, gitroot: &str, schemes: &[String], jobs: usize, launcher_settings: &[String]) -> Vec<BuildResult> {
+    let queue = Mutex::new(schemes.iter().enumerate());
+    let results = Mutex::new(Vec::new());
+    thread::scope(|scope| {
+        for _ in 0..jobs.min(schemes.len()) {
+            scope.spawn(|| loop {
+                let next = queue.lock().expect("Build queue lock poisoned").next();
+                let Some((index, scheme)) = next else {
+                    break;
+                };
+                say!("Building scheme {}...", scheme);
+                let result_bundle = format!(".bundle/{}.xcresult", scheme);
+                // Concurrent builds can't share a build database, so each scheme gets its own DerivedData
+                let derived_data = (jobs > 1).then(|| derived_data_root().join(format!("{}-sass-{}", config.workspace_name, scheme)));
+                let result = build_scheme(config, gitroot, scheme, &result_bundle, derived_data.as_deref(), launcher_settings);
+                results.lock().expect("Build results lock poisoned").push((index, result));
+            });
+        }
+    });
+    let mut results = results.into_inner().expect("Build results lock poisoned");
+    results.sort_by_key(|(index, _)| *index);
+    results.into_iter().map(|(_, result)| result).collect()
+}
+
+fn print_build_summary(results: &[BuildResult]) {
+    let width = results.iter().map(|result| result.scheme.len()).max().unwrap_or(0).max("Scheme".len());
+    say!("{:width$}  Result  Duration", "Scheme", width = width);
+    for result in results {
+        let status = if result.success { "passed" } else { "failed" };
+        say!("{:width$}  {:6}  {:.1}s", result.scheme, status, result.duration.as_secs_f64(), width = width);
+    }
+    let failed = results.iter().filter(|result| !result.success).count();
+    say!("{} of {} schemes built successfully.", results.len() - failed, results.len());
+    let schemes: Vec<serde_json::Value> = results.iter().map(|result| json!({
+        "scheme": result.scheme,
+        "success": result.success,
+        "duration_ms": result.duration.as_millis() as u64,
+    })).collect();
+    report::result("build_summary", json!({ "schemes": schemes }));
+}
+
+fn test() {
+    report::step("test", "Testing...");
+    let config: Config = load_config();
+    let gitroot = git_root();
+    let root = Path::new(&gitroot);
+    let result_bundle = ".bundle-tests";
+    retry::remove_dir_all(&root.join(result_bundle));
+    let action = if has_flag("without-building") { "test-without-building" } else { "test" };
+    let destination = config.test_destination.as_ref().unwrap_or(&config.destination);
+    if destination.starts_with("generic/") {
+        warn!("Warning: tests need a concrete destination, set test_destination in the config.");
+    }
+    let mut args: Vec<String> = [action, "-workspace", format!("{}.xcworkspace", config.workspace_name).as_str(), "-scheme", config.scheme.as_str(), "-destination", destination.as_str(), "-resultBundlePath", result_bundle]
+        .iter()
+        .map(|arg| arg.to_string())
+        .collect();
+    if let Some(test_plan) = arg_value("test-plan").or(config.test_plan.clone()) {
+        args.push("-testPlan".to_string());
+        args.push(test_plan);
+    }
+    let (status, log) = run_streamed(xcodebuild(&config.xcodebuild_args, &config.build_settings)
+        .args(args)
+        .current_dir(root))
+        .expect("failed to execute process");
+
+    let summary = match xcresult::load_tests(&root.join(result_bundle)) {
+        Ok(summary) => summary,
+        Err(error) => {
+            log_parser::parse(&log).print();
+            panic!("Could not read test results: {}", error);
+        }
+    };
+    summary.print();
+    report::result("tests", json!({ "passed": summary.passed, "failed": summary.failed, "skipped": summary.skipped }));
+    let junit_path = arg_value("junit").map(PathBuf::from).unwrap_or_else(|| root.join("test-results.xml"));
+    match junit::write(&summary, &junit_path) {
+        Ok(_result) => say!("Wrote JUnit results to {}", junit_path.display()),
+        Err(error) => warn!("Error writing {}: {}", junit_path.display(), error),
+    }
+    if summary.failed > 0 {
+        panic!("{} tests failed", summary.failed);
+    }
+    if !status.success() {
+        panic!("xcodebuild test exited with {}", status);
+    }
+}
+
 fn rebuild_build_server() {
-    println!("Generating buildServer.json...");
-    let config: Config = get_config("sass".to_string());
+    let config: Config = load_config();
     let gitroot = git_root();
+    let root = Path::new(&gitroot);
     let workspace = config.workspace_name;
     let scheme = config.scheme;
-    let output = Command::new("xcode-build-server")
+    let workspace_exists = !workspace.is_empty() && root.join(format!("{}.xcworkspace", workspace)).exists();
+    if !workspace_exists && root.join("Package.swift").exists() {
+        say!("No workspace found, configuring sourcekit-lsp for the Swift package...");
+        build_server::setup_swiftpm(root);
+        return;
+    }
+
+    report::step("rebuild_build_server", "Generating buildServer.json...");
+    let output = match Command::new("xcode-build-server")
         .args(["config", "-workspace", format!("{}.xcworkspace", workspace).as_str(), "-scheme", scheme.as_str()])
-        .current_dir(gitroot)
-        .output()
-        .expect("failed to execute process");
-    println!("{}", String::from_utf8(output.stdout).expect("Error constructing build server"));
+        .current_dir(root)
+        .reported_output() {
+        Ok(output) => output,
+        Err(error) if error.kind() == io::ErrorKind::NotFound => panic!("xcode-build-server not found on PATH, install it with `brew install xcode-build-server`"),
+        Err(error) => panic!("failed to execute xcode-build-server: {}", error),
+    };
+    say!("{}", String::from_utf8(output.stdout).expect("Error constructing build server"));
+    if !output.status.success() {
+        panic!("xcode-build-server failed: {}", String::from_utf8_lossy(&output.stderr));
+    }
+    match build_server::validate(&root.join("buildServer.json"), &workspace, &scheme) {
+        Ok(warnings) => {
+            for warning in warnings {
+                warn!("Warning: {}", warning);
+            }
+        }
+        Err(error) => panic!("Invalid buildServer.json: {}", error),
+    }
 }
 
-fn install_deps_script() -> Option<()> {
-    println!("Executing dependency installation script.");
-    let config: Config = get_config("sass".to_string());
-    let git_root = git_root();
-    let script_dir = git_root.clone() + config.post_install_script_location?.as_str();
-    Command::new("sh")
-        .args([script_dir])
-        .current_dir(git_root)
-        .output()
-        .expect("failed to execute process");
-    Some(())
+// Runs the command, echoing its output as it arrives, and returns its exit status and combined output
+fn run_streamed(command: &mut Command) -> io::Result<(ExitStatus, String)> {
+    run_streamed_with_timeout(command, None)
+}
+
+// run_streamed, killing the command and anything it started once the timeout passes
+fn run_streamed_with_timeout(command: &mut Command, timeout: Option<Duration>) -> io::Result<(ExitStatus, String)> {
+    if timeout.is_some() {
+        // Its own process group, so the kill reaches child processes too
+        command.process_group(0);
+    }
+    let mut child = command.stdout(Stdio::piped()).stderr(Stdio::piped()).spawn()?;
+    let stdout = child.stdout.take().expect("stdout is piped");
+    let stderr = child.stderr.take().expect("stderr is piped");
+    let output = Mutex::new(String::new());
+    let started = Instant::now();
+    let status = thread::scope(|scope| {
+        scope.spawn(|| echo_lines(stderr, &output, true));
+        scope.spawn(|| echo_lines(stdout, &output, false));
+        let Some(timeout) = timeout else {
+            return child.wait();
+        };
+        loop {
+            if let Some(status) = child.try_wait()? {
+                return Ok(status);
+            }
+            if started.elapsed() >= timeout {
+                let _ = Command::new("kill").args(["-KILL", "--", &format!("-{}", child.id())]).status();
+                child.wait()?;
+                return Err(io::Error::new(io::ErrorKind::TimedOut, format!("timed out after {}s", timeout.as_secs())));
+            }
+            thread::sleep(Duration::from_millis(100));
+        }
+    })?;
+    report::subprocess(command, status);
+    Ok((status, output.into_inner().expect("Output lock poisoned")))
+}
+
+fn echo_lines(reader: impl Read, output: &Mutex<String>, to_stderr: bool) {
+    for line in BufReader::new(reader).lines().map_while(Result::ok) {
+        // With --json, stdout is reserved for events
+        if to_stderr || report::json_enabled() {
+            eprintln!("{}", line);
+        } else {
+            println!("{}", line);
+        }
+        let mut output = output.lock().expect("Output lock poisoned");
+        output.push_str(&line);
+        output.push('\n');
+    }
+}
+
+// Starts an xcodebuild invocation with the configured extra arguments and build settings,
+// plus anything passed after `--` on the command line
+fn xcodebuild(extra_args: &[String], build_settings: &HashMap<String, String>) -> Command {
+    let mut command = Command::new("xcodebuild");
+    if is_offline() {
+        command.args(["-skipPackageUpdates", "-disableAutomaticPackageResolution"]);
+    }
+    command.args(extra_args);
+    let mut settings: Vec<(&String, &String)> = build_settings.iter().collect();
+    settings.sort();
+    command.args(settings.into_iter().map(|(key, value)| format!("{}={}", key, value)));
+    command.args(passthrough_args());
+    command
+}
+
+// Returns whether any script was configured, panics when one fails
+fn install_deps_script() -> bool {
+    report::step("install_deps_script", "Executing dependency installation script.");
+    let config: Config = load_config();
+    deps_scripts::run_all(&config, &git_root())
+}
+
+#[derive(Clone, Copy)]
+enum DerivedDataPart {
+    PrecompiledHeaders,
+    ModuleCache,
+    Index,
+    Packages,
+    Products,
+    Logs,
+}
+
+impl DerivedDataPart {
+    const NAMES: [&'static str; 6] = ["headers", "modulecache", "index", "packages", "products", "logs"];
+
+    fn from_name(name: &str) -> Option<DerivedDataPart> {
+        match name {
+            "headers" => Some(DerivedDataPart::PrecompiledHeaders),
+            "modulecache" => Some(DerivedDataPart::ModuleCache),
+            "index" => Some(DerivedDataPart::Index),
+            "packages" => Some(DerivedDataPart::Packages),
+            "products" => Some(DerivedDataPart::Products),
+            "logs" => Some(DerivedDataPart::Logs),
+            _ => None,
+        }
+    }
+
+    // Location of this part relative to a project's DerivedData folder
+    fn relative_path(&self) -> PathBuf {
+        match self {
+            DerivedDataPart::PrecompiledHeaders => ["Build", "Intermediates.noindex", "PrecompiledHeaders"].iter().collect(),
+            DerivedDataPart::ModuleCache => PathBuf::from("ModuleCache.noindex"),
+            DerivedDataPart::Index => PathBuf::from("Index.noindex"),
+            DerivedDataPart::Packages => PathBuf::from("SourcePackages"),
+            DerivedDataPart::Products => ["Build", "Products"].iter().collect(),
+            DerivedDataPart::Logs => PathBuf::from("Logs"),
+        }
+    }
 }
 
-fn wipe_derived_data(intermediates_only: bool) {
-    println!("Cleaning DerivedData...");
+// Removes the given parts (or the whole folder if parts is empty) of every DerivedData project folder,
+// restricted to folders belonging to `project` if one is given.
+fn wipe_derived_data(parts: &[DerivedDataPart], project: Option<&str>) {
+    report::step("wipe_derived_data", "Cleaning DerivedData...");
     let paths = get_derived_data_folders().unwrap_or_else(|_| Vec::new());
     let xcode_dd_search = Regex::new(r"^.*-.*$").expect("DerivedData regex failed to parse");
+    if project.is_none() && parts.iter().any(|part| matches!(part, DerivedDataPart::ModuleCache)) {
+        // Recent Xcode versions share a single module cache between all projects
+        let shared_module_cache = derived_data_root().join("ModuleCache.noindex");
+        if shared_module_cache.exists() {
+            retry::remove_dir_all(&shared_module_cache);
+        }
+    }
     for path in paths {
         let reg_str = path.to_str().expect("Unusual string format in derived data directory!");
         let correct_format = match xcode_dd_search.captures(reg_str) {
@@ -221,38 +844,35 @@ fn wipe_derived_data(intermediates_only: bool) {
         if !correct_format {
             continue
         }
+        if let Some(project) = project {
+            // Folders are named <project>-<hash>, and project names may themselves contain dashes
+            let folder_name = path.file_name().map(|name| name.to_string_lossy().into_owned()).unwrap_or_default();
+            if folder_name.rsplit_once('-').map(|(name, _hash)| name) != Some(project) {
+                continue;
+            }
+        }
 
         let lockfile_path = path.join("info.plist");
         if !lockfile_path.exists() {
-            println!("Skipping {}: missing {}", path.display(), lockfile_path.display());
+            warn!("Skipping {}: missing {}", path.display(), lockfile_path.display());
             continue;
         }
         if !can_lock(&lockfile_path) {
-            println!("Skipping {}: could not acquire lock on {}", path.display(), lockfile_path.display());
+            warn!("Skipping {}: could not acquire lock on {}", path.display(), lockfile_path.display());
             continue;
         }
 
-        let retry_dur = time::Duration::from_millis(999);
-        let retry_cap = 15;
-        let mut target_path = path;
-        if intermediates_only {
-            target_path.push("Build");
-            target_path.push("Intermediates.noindex");
-            target_path.push("PrecompiledHeaders");
+        if parts.is_empty() {
+            retry::remove_dir_all(&path);
+            continue;
         }
-        let mut removed = false;
-        for i in 1..retry_cap {
-            match fs::remove_dir_all(target_path.clone()) {
-                Ok(_some) => {
-                    removed = true;
-                    break;
-                }
-                Err(error) => println!("Error: {}. Directory could be locked, retrying. Attempt {} of 15.", error, i),
+        for part in parts {
+            let target_path = path.join(part.relative_path());
+            if !target_path.exists() {
+                continue;
             }
-            thread::sleep(retry_dur);
-        }
-        if !removed {
-            println!("Failed to remove {} after {} attempts.", target_path.display(), retry_cap - 1);
+            say!("Removing {}", target_path.display());
+            retry::remove_dir_all(&target_path);
         }
     }
 }
@@ -265,17 +885,18 @@ fn can_lock(path: &Path) -> bool {
             true
         }
         Err(error) => {
-            println!("Could not lock {}: {}", path.display(), error);
+            warn!("Could not lock {}: {}", path.display(), error);
             false
         }
     }
 }
 
 fn nuke_simulators() {
+    report::step("nuke_simulators", "Deleting old simulators...");
     // Delete old simulators
     let output = Command::new("xcrun")
         .args(["simctl", "list"])
-        .output()
+        .reported_output()
         .expect("failed to execute process");
     let osver_regex = Regex::new(r"(?:-- iOS)\s*(?<ver>\S*)\s*--").expect("Package search regex failed to parse");
     let str_output = String::from_utf8(output.stdout).expect("Did not decode properly.");
@@ -288,18 +909,18 @@ fn nuke_simulators() {
     let matches = id_regex.captures_iter(id_substr);
     for id in matches {
         let sim_id = id.name("id").expect("Error unwrapping sim id match").as_str();
-        println!("Deleting old simulator {}", sim_id);
+        say!("Deleting old simulator {}", sim_id);
         Command::new("xcrun")
             .args(["simctl", "delete", sim_id])
-            .output()
+            .reported_output()
             .expect("Failed to delete simulator");
     }
 
-    println!("Deleting simulator symbolication caches.");
+    say!("Deleting simulator symbolication caches.");
 
     Command::new("xcrun")
         .args(["simctl", "shutdown", "all"])
-        .output()
+        .reported_output()
         .expect("Failed to shutdown simulators");
 
     let devices_dir = shellexpand::tilde("~/Library/Developer/CoreSimulator/Devices/").into_owned();
@@ -310,18 +931,59 @@ fn nuke_simulators() {
             Err(_) => continue,
         };
         if path.ends_with("Caches/com.apple.coresymbolicationd") {
-            println!("Removing {}", path.display());
-            match fs::remove_dir_all(&path) {
-                Ok(_) => (),
-                Err(error) => println!("Error removing {}: {}", path.display(), error),
-            }
+            say!("Removing {}", path.display());
+            retry::remove_dir_all(&path);
+        }
+    }
+}
+
+// Resolves the DerivedData root from the config, then Xcode's own preferences, then the default location
+fn derived_data_root() -> PathBuf {
+    let config: Config = load_config();
+    if let Some(location) = config.derived_data_location {
+        return resolve_derived_data_location(&location);
+    }
+    if let Some(location) = xcode_derived_data_location() {
+        return resolve_derived_data_location(&location);
+    }
+    PathBuf::from(shellexpand::tilde("~/Library/Developer/Xcode/DerivedData/").into_owned())
+}
+
+// Relative locations are relative to the workspace, which lives in the git root
+fn resolve_derived_data_location(location: &str) -> PathBuf {
+    let path = PathBuf::from(shellexpand::tilde(location).into_owned());
+    if path.is_absolute() {
+        path
+    } else {
+        PathBuf::from(git_root()).join(path)
+    }
+}
+
+// Reads IDECustomDerivedDataLocation, set when DerivedData is moved in Xcode's Locations settings
+fn xcode_derived_data_location() -> Option<String> {
+    let plist_path = shellexpand::tilde("~/Library/Preferences/com.apple.dt.Xcode.plist").into_owned();
+    let lookups: [(&str, Vec<&str>); 2] = [
+        ("defaults", vec!["read", "com.apple.dt.Xcode", "IDECustomDerivedDataLocation"]),
+        ("plutil", vec!["-extract", "IDECustomDerivedDataLocation", "raw", "-o", "-", plist_path.as_str()]),
+    ];
+    for (program, args) in lookups {
+        let output = match Command::new(program).args(args).output() {
+            Ok(output) => output,
+            Err(_) => continue,
+        };
+        if !output.status.success() {
+            continue;
+        }
+        let location = String::from_utf8_lossy(&output.stdout).trim().to_string();
+        if !location.is_empty() {
+            return Some(location);
         }
     }
+    None
 }
 
 fn get_derived_data_folders() -> io::Result<Vec<PathBuf>> {
-    let derived_data_str = shellexpand::tilde("~/Library/Developer/Xcode/DerivedData/").into_owned().to_string();
-    let entries = fs::read_dir(derived_data_str)?
+    let entries = fs::read_dir(derived_data_root())?
         .map(|res| res.map(|e| e.path()))
         .collect::<Result<Vec<_>, io::Error>>()?;
 
@@ -329,9 +991,9 @@ fn get_derived_data_folders() -> io::Result<Vec<PathBuf>> {
 }
 
 fn wipe_project_derived_data() {
-    let config: Config = get_config("sass".to_string());
+    let config: Config = load_config();
     let derived_data_str = shellexpand::tilde(&config.project_derived_data_recursive_root.expect("Error - project recursive root not specified in config")).into_owned().to_string();
-    println!("Walking directory at {}", &derived_data_str);
+    say!("Walking directory at {}", &derived_data_str);
     let walker = WalkDir::new(&derived_data_str);
     for entry in walker {
         let path = match entry {
@@ -340,134 +1002,233 @@ fn wipe_project_derived_data() {
         };
         if let Some(file_name) = path.file_name() &&
             file_name.to_string_lossy().starts_with("DerivedData-") {
-            println!("Removing {}", path.display());
-            match fs::remove_dir_all(&path) {
-                Ok(_) => (),
-                Err(error) => println!("Error removing {}: {}", path.display(), error),
-            }
+            say!("Removing {}", path.display());
+            retry::remove_dir_all(&path);
         }
     }
 }
 
 fn wipe_pod_cache_hard() {
-    println!("Hard clearing pod cache...");
+    report::step("wipe_pod_cache_hard", "Hard clearing pod cache...");
+    if is_offline() {
+        warn!("Offline: skipping pod cache cleanup, pods could not be downloaded again.");
+        return;
+    }
     let gitroot = git_root();
     let pods_dir = gitroot.clone() + "/Pods/";
     let lockfile_path = gitroot + "/Podfile.lock";
     let cocoa_dir_string = shellexpand::tilde("~/Library/Caches/CocoaPods/").into_owned().to_string();
     let lockfile = Path::new(&lockfile_path);
     if lockfile.exists() && !can_lock(lockfile) {
-        println!("Skipping Pod cleanup: could not acquire lock on {}", lockfile.display());
+        warn!("Skipping Pod cleanup: could not acquire lock on {}", lockfile.display());
         return;
     }
 
-    match fs::remove_file(lockfile_path) {
-        Ok(_result) => (),
-        Err(error) if error.kind() == io::ErrorKind::NotFound => (),
-        Err(error) => println!("Error: {}", error),
-    }
-    match fs::remove_dir_all(cocoa_dir_string) {
-        Ok(_result) => (),
-        Err(error) => println!("Error: {}", error),
-    }
-    match fs::remove_dir_all(pods_dir) {
-        Ok(_result) => (),
-        Err(error) => println!("Error: {}", error),
-    }
+    retry::remove_file(lockfile);
+    retry::remove_dir_all(Path::new(&cocoa_dir_string));
+    retry::remove_dir_all(Path::new(&pods_dir));
 }
 
 fn wipe_pods() {
-    println!("Clearing pod cache...");
-    let uses_bundler = _uses_bundler();
-    if uses_bundler {
-        Command::new("bundle")
-            .args(["exec", "pod", "cache", "clean", "--all"])
-            .current_dir(git_root())
-            .output()
-            .expect("failed to execute process");
-    } else {
-        Command::new("pod")
-            .args(["cache", "clean", "--all"])
-            .current_dir(git_root())
-            .output()
-            .expect("failed to execute process");
+    report::step("wipe_pods", "Clearing pod cache...");
+    if is_offline() {
+        warn!("Offline: skipping pod cache cleanup, pods could not be downloaded again.");
+        return;
     }
+    pod_command()
+        .args(["cache", "clean", "--all"])
+        .reported_output()
+        .expect("failed to execute process");
 }
 
 fn install_pods() {
-    println!("Installing pods...");
-    let uses_bundler = _uses_bundler();
-    if uses_bundler {
-        let output = Command::new("bundle")
-            .args(["exec", "pod", "install", "--repo-update"])
-            .current_dir(git_root())
-            .output()
-            .expect("failed to execute process");
-        let output_str = String::from_utf8(output.stdout).expect("Did not decode properly.");
-        println!("{}", output_str);
+    report::step("install_pods", "Installing pods...");
+    // Without --repo-update pod install only uses the specs it already has
+    let mut args = vec!["install"];
+    if is_offline() {
+        warn!("Offline: installing pods without updating the specs repo.");
     } else {
-        let output = Command::new("pod")
-            .args(["install", "--repo-update"])
-            .current_dir(git_root())
-            .output()
-            .expect("failed to execute process");
-        let output_str = String::from_utf8(output.stdout).expect("Did not decode properly.");
-        println!("{}", output_str);
+        args.push("--repo-update");
     }
+    let output = retry::network_output(pod_command().args(&args))
+        .expect("failed to execute process");
+    let output_str = String::from_utf8(output.stdout).expect("Did not decode properly.");
+    say!("{}", output_str);
+}
+
+// `pod`, run through bundler when the project uses it, in the git root
+fn pod_command() -> Command {
+    let mut command = if _uses_bundler() {
+        let mut command = Command::new("bundle");
+        command.args(["exec", "pod"]);
+        command
+    } else {
+        Command::new("pod")
+    };
+    command.current_dir(git_root());
+    command
 }
 
 // TODO: apply to subprojects
 fn clean_packages() {
-    let root = git_root();
-    let package_regex = Regex::new(r"ackage\.swift").expect("Package search regex failed to parse");
-    let build_regex = Regex::new(r"\.build").expect("Build dir search regex failed to parse");
-    let mut package_roots: Vec<PathBuf> = Vec::new();
-    let walker = WalkDir::new(root);
-    for entry in walker {
-        let path = entry.unwrap().into_path();
-
-        let has_package = match package_regex.captures(&path.to_string_lossy()) {
-            Some(_expr) => true,
-            None => false,
-        };
-        let has_build_dir = match build_regex.captures(&path.to_string_lossy()) {
-            Some(_expr) => true,
-            None => false,
-        };
-        if has_package && !has_build_dir {
-            package_roots.push(path);
+    report::step("clean_packages", "Cleaning packages...");
+    let package_roots = package_roots();
+    // The cache is shared by every package of the user, purging it once is enough
+    if let Some(package_loc) = package_roots.first() {
+        if is_offline() {
+            warn!("Offline: keeping the SwiftPM cache, packages could not be downloaded again.");
+        } else {
+            Command::new("swift")
+                .args(["package", "purge-cache"])
+                .current_dir(package_loc)
+                .reported_output()
+                .expect("failed to execute process");
         }
     }
-
-    for mut package_loc in package_roots {
-        package_loc.pop();
-        println!("Executing package clean in {}", &package_loc.to_string_lossy());
-        Command::new("swift")
-            .args(["package", "purge-cache"])
-            .current_dir(&package_loc)
-            .output()
-            .expect("failed to execute process");
+    for package_loc in package_roots {
+        say!("Executing package clean in {}", &package_loc.to_string_lossy());
         Command::new("swift")
             .args(["package", "reset"])
             .current_dir(&package_loc)
-            .output()
+            .reported_output()
             .expect("failed to execute process");
         Command::new("swift")
             .args(["package", "clean"])
             .current_dir(&package_loc)
-            .output()
+            .reported_output()
             .expect("failed to execute process");
     }
 }
 
+// Resolves every package to exactly the versions pinned in its Package.resolved
 fn install_packages() {
+    report::step("install_packages", "Installing packages...");
+    resolve_workspace_packages();
+    for package_loc in package_roots() {
+        say!("Executing package build in {}", &package_loc.to_string_lossy());
+        let mut args = vec!["package", "resolve"];
+        // Packages without dependencies have no lockfile to hold them to
+        let has_lockfile = package_loc.join("Package.resolved").exists();
+        if has_lockfile {
+            args.push("--disable-automatic-resolution");
+        }
+        let output = if is_offline() {
+            args.push("--skip-update");
+            Command::new("swift").args(&args).current_dir(&package_loc).reported_output()
+        } else {
+            retry::network_output(Command::new("swift").args(&args).current_dir(&package_loc))
+        }.expect("failed to execute process");
+        if !output.status.success() {
+            let stderr = String::from_utf8_lossy(&output.stderr);
+            if has_lockfile {
+                panic!("Could not resolve {} to its Package.resolved, run sass --packages-update to update it: {}", package_loc.display(), stderr);
+            }
+            panic!("Could not resolve {}: {}", package_loc.display(), stderr);
+        }
+    }
+}
+
+// Resolves the remote packages attached to the configured workspace and its projects,
+// which Xcode pins in xcshareddata/swiftpm/Package.resolved
+fn resolve_workspace_packages() {
+    let config: Config = load_config();
+    let gitroot = git_root();
+    let workspace_file = format!("{}.xcworkspace", config.workspace_name);
+    if config.workspace_name.is_empty() || !Path::new(&gitroot).join(&workspace_file).exists() {
+        return;
+    }
+    let lockfiles = workspace_package_lockfiles(Path::new(&gitroot));
+    if lockfiles.is_empty() {
+        return;
+    }
+    for lockfile in &lockfiles {
+        say!("Resolving workspace packages pinned in {}", lockfile.display());
+    }
+    let mut command = xcodebuild(&config.xcodebuild_args, &config.build_settings);
+    command
+        .args(["-resolvePackageDependencies", "-onlyUsePackageVersionsFromResolvedFile", "-workspace", workspace_file.as_str()])
+        .current_dir(&gitroot);
+    if !config.scheme.is_empty() {
+        command.args(["-scheme", config.scheme.as_str()]);
+    }
+    let output = retry::network_output(&mut command).expect("failed to execute process");
+    if !output.status.success() {
+        panic!("Could not resolve the workspace's packages to their Package.resolved: {}", String::from_utf8_lossy(&output.stderr));
+    }
+}
+
+// Xcode keeps the checkouts of workspace packages in the project's DerivedData
+fn clean_workspace_packages() {
+    let config: Config = load_config();
+    if config.workspace_name.is_empty() || workspace_package_lockfiles(Path::new(&git_root())).is_empty() {
+        return;
+    }
+    wipe_derived_data(&[DerivedDataPart::Packages], Some(&config.workspace_name));
+}
+
+fn workspace_package_lockfiles(root: &Path) -> Vec<PathBuf> {
+    project_files(root)
+        .filter(|path| path.ends_with("xcshareddata/swiftpm/Package.resolved"))
+        .collect()
+}
+
+// Everything under the project root, skipping pods, build output, DerivedData and git's internals
+fn project_files(root: &Path) -> impl Iterator<Item = PathBuf> {
+    let skipped_dirs = ["Pods", ".build", "DerivedData", ".git"];
+    WalkDir::new(root)
+        .into_iter()
+        .filter_entry(move |entry| !skipped_dirs.contains(&entry.file_name().to_string_lossy().as_ref()))
+        .flatten()
+        .map(|entry| entry.into_path())
+}
+
+// Updates dependencies past their pinned versions, all of them or just the named ones
+fn update_packages() {
+    report::step("update_packages", "Updating packages...");
+    if is_offline() {
+        warn!("Offline: skipping swift package update.");
+        return;
+    }
+    let names = operands("packages-update", "pu");
+    let mut updated: Vec<&String> = Vec::new();
+    for package_loc in package_roots() {
+        // swift package update fails on names the package doesn't depend on, so each root only gets the ones it pins
+        let package_names: Vec<&String> = if names.is_empty() {
+            Vec::new()
+        } else {
+            let pinned = spm_cache::pinned_identities(&package_loc.join("Package.resolved"));
+            let package_names: Vec<&String> = names.iter().filter(|name| pinned.contains(&name.to_lowercase())).collect();
+            if package_names.is_empty() {
+                continue;
+            }
+            package_names
+        };
+        updated.extend(&package_names);
+        say!("Executing package update in {}", &package_loc.to_string_lossy());
+        let output = retry::network_output(Command::new("swift")
+            .args(["package", "update"])
+            .args(package_names)
+            .current_dir(&package_loc))
+            .expect("failed to execute process");
+        if !output.status.success() {
+            panic!("Could not update {}: {}", package_loc.display(), String::from_utf8_lossy(&output.stderr));
+        }
+    }
+    let unpinned: Vec<&str> = names.iter().filter(|name| !updated.contains(name)).map(String::as_str).collect();
+    if !unpinned.is_empty() {
+        warn!("No Package.resolved pins {}", unpinned.join(", "));
+    }
+}
+
+// Directories containing a Package.swift, ignoring checkouts in build directories
+fn package_roots() -> Vec<PathBuf> {
     let root = git_root();
     let package_regex = Regex::new(r"ackage\.swift").expect("Package search regex failed to parse");
     let build_regex = Regex::new(r"\.build").expect("Build dir search regex failed to parse");
     let mut package_roots: Vec<PathBuf> = Vec::new();
     let walker = WalkDir::new(root);
     for entry in walker {
-        let path = entry.unwrap().into_path();
+        let mut path = entry.unwrap().into_path();
 
         let has_package = match package_regex.captures(&path.to_string_lossy()) {
             Some(_expr) => true,
@@ -478,31 +1239,24 @@ fn install_packages() {
             None => false,
         };
         if has_package && !has_build_dir {
+            path.pop();
             package_roots.push(path);
         }
     }
+    package_roots
+}
 
-    for mut package_loc in package_roots {
-        package_loc.pop();
-        println!("Executing package build in {}", &package_loc.to_string_lossy());
-        Command::new("swift")
-            .args(["package", "resolve"])
-            .current_dir(&package_loc)
-            .output()
-            .expect("failed to execute process");
-        Command::new("swift")
-            .args(["package", "update"])
-            .current_dir(&package_loc)
-            .output()
-            .expect("failed to execute process");
-    }
+// Set with --offline or `offline = true` in the config
+fn is_offline() -> bool {
+    let config: Config = load_config();
+    config.offline || has_flag("offline")
 }
 
 // Checks if you use a bundler
 fn _uses_bundler() -> bool {
     let output = Command::new("gem")
         .args(["list", "--local"])
-        .output()
+        .reported_output()
         .expect("failed to execute process");
 
     let gems = String::from_utf8(output.stdout).unwrap_or(String::from(""));
@@ -528,8 +1282,50 @@ fn git_root() -> String {
     str
 }
 
+// Arguments meant for sass itself, i.e. everything before `--`
+fn sass_args() -> impl Iterator<Item = String> {
+    env::args().skip(1).take_while(|arg| arg != "--")
+}
+
+// Arguments after `--`, forwarded to xcodebuild
+fn passthrough_args() -> Vec<String> {
+    env::args().skip_while(|arg| arg != "--").skip(1).collect()
+}
+
+// Returns the value passed as `--name value` or `--name=value`, if any
+fn arg_value(name: &str) -> Option<String> {
+    let flag = format!("--{}", name);
+    let prefix = format!("{}=", flag);
+    let mut args = sass_args();
+    while let Some(arg) = args.next() {
+        if arg == flag {
+            return args.next();
+        }
+        if let Some(value) = arg.strip_prefix(&prefix) {
+            return Some(value.to_string());
+        }
+    }
+    None
+}
+
+// Values following a command's flag, e.g. the paths in `sass --results-show a b`
+fn operands(long_flag: &str, short_flag: &str) -> Vec<String> {
+    let long_flag = format!("--{}", long_flag);
+    let short_flag = format!("-{}", short_flag);
+    sass_args()
+        .skip_while(|arg| *arg != long_flag && *arg != short_flag)
+        .skip(1)
+        .take_while(|arg| !arg.starts_with('-'))
+        .collect()
+}
+
+fn has_flag(name: &str) -> bool {
+    let flag = format!("--{}", name);
+    sass_args().any(|arg| arg == flag)
+}
+
 fn update_templates() {
-    println!("Updating templates.");
+    report::step("update_templates", "Updating templates.");
     let dir_path_string = shellexpand::tilde("~/.config/sass/templates/").into_owned().to_string();
     let xcode_path_string = shellexpand::tilde("~/Library/Developer/Xcode/Templates").into_owned().to_string();
     let dir_path = Path::new(&dir_path_string);
@@ -538,25 +1334,29 @@ fn update_templates() {
     if dir_exists {
         let mut xcode_pathbuf = xcode_path.to_path_buf();
         xcode_pathbuf.push("sass");
-        _ = fs::remove_dir_all(&xcode_pathbuf);
+        retry::remove_dir_all(&xcode_pathbuf);
         match copy_dir(dir_path, xcode_pathbuf.as_path()) {
             Ok(_result) => (),
-            Err(error) => println!("Error: {}", error),
+            Err(error) => warn!("Error: {}", error),
         }
     }
 }
 
 impl Runtime {
             pub fn gen_completions_2(&self) -> ::std::string::String {
-                let arg_block: &Vec<String> = &self.commands.clone().into_iter().map(|command| {
+                let mut arg_block: Vec<String> = self.commands.clone().into_iter().map(|command| {
                     let mut filtered_description = command.description.replace("\"", "");
                     filtered_description = filtered_description.replace("\'", "");
-                    return format!("\t'(-{} --{})'{{-{},--{}}}'[{}]'", command.short_flag, command.long_flag, command.short_flag, command.long_flag, filtered_description);
+                    format!("\t'(-{} --{})'{{-{},--{}}}'[{}]'", command.short_flag, command.long_flag, command.short_flag, command.long_flag, filtered_description)
                 }).collect();
+                let config: Config = get_config("sass".to_string());
+                let mut profile_names: Vec<&String> = config.profiles.keys().collect();
+                profile_names.sort();
+                let profile_names: Vec<&str> = profile_names.into_iter().map(|name| name.as_str()).collect();
+                arg_block.push(format!("\t'--profile[build profile from the config]:profile:({})'", profile_names.join(" ")));
                 let args_block = arg_block.join("\n");
                 let name = env!("CARGO_PKG_NAME");
-                return format!("
-#compdef {name}
+                format!("#compdef {name}
 local -a args
 args=(
 {args_block}
@@ -0,0 +1,265 @@
+use std::collections::HashMap;
+use std::fs;
+use std::io;
+use std::path::Path;
+use std::process::Command;
+
+use serde_json::json;
+
+use crate::report::ReportedCommand;
+use crate::{git_root, has_flag, is_offline, operands, pod_command, report, retry};
+
+// The parts of a Podfile.lock (or the identical Pods/Manifest.lock) sass cares about
+pub struct Lockfile {
+    // Every pod and subspec with its version, e.g. ("Firebase/Core", "10.0.0")
+    pub pods: Vec<(String, String)>,
+    pub spec_checksums: HashMap<String, String>,
+    pub podfile_checksum: Option<String>,
+}
+
+impl Lockfile {
+    // Pod names without subspecs, which is what the cache and Pods/ are keyed by
+    pub fn root_pods(&self) -> Vec<String> {
+        let mut roots: Vec<String> = Vec::new();
+        for (name, _) in &self.pods {
+            let root = name.split('/').next().unwrap_or(name).to_string();
+            if !roots.contains(&root) {
+                roots.push(root);
+            }
+        }
+        roots
+    }
+}
+
+pub fn load_lockfile(path: &Path) -> io::Result<Lockfile> {
+    Ok(parse_lockfile(&fs::read_to_string(path)?))
+}
+
+// Lockfiles are YAML, but only ever in the shape CocoaPods writes them, so a line based parse does
+pub fn parse_lockfile(contents: &str) -> Lockfile {
+    let mut lockfile = Lockfile { pods: Vec::new(), spec_checksums: HashMap::new(), podfile_checksum: None };
+    let mut section = "";
+    for line in contents.lines() {
+        if !line.starts_with(' ') {
+            let (key, value) = line.split_once(':').unwrap_or((line, ""));
+            section = key.trim();
+            if section == "PODFILE CHECKSUM" {
+                lockfile.podfile_checksum = Some(value.trim().to_string());
+            }
+            continue;
+        }
+        match section {
+            // Top level entries are indented by two spaces, their dependencies by four
+            "PODS" => {
+                let Some(entry) = line.strip_prefix("  - ") else {
+                    continue;
+                };
+                let entry = unquote(entry.trim_end_matches(':'));
+                let (name, version) = entry.split_once(" (").unwrap_or((entry, ""));
+                lockfile.pods.push((name.to_string(), version.trim_end_matches(')').to_string()));
+            }
+            "SPEC CHECKSUMS" => {
+                if let Some((name, checksum)) = line.trim().rsplit_once(": ") {
+                    lockfile.spec_checksums.insert(unquote(name).to_string(), checksum.trim().to_string());
+                }
+            }
+            _ => (),
+        }
+    }
+    lockfile
+}
+
+fn unquote(text: &str) -> &str {
+    text.trim().trim_matches('"')
+}
+
+pub fn pods() {
+    let args = operands("pods", "po");
+    match args.first().map(String::as_str) {
+        Some("clean") => clean(&args[1..], has_flag("from-lockfile")),
+        None | Some("check") => {
+            let fix = has_flag("fix");
+            if !check(fix) && !fix {
+                panic!("Pods are out of sync with Podfile.lock, run sass --pods check --fix");
+            }
+        }
+        Some(action) => warn!("Unknown pods action {}. Expected clean <PodName...>, clean --from-lockfile or check [--fix].", action),
+    }
+}
+
+// Removes the given pods from the CocoaPods cache and Pods/, then lets pod install fetch just those again
+fn clean(names: &[String], from_lockfile: bool) {
+    if is_offline() {
+        warn!("Offline: skipping pod cleanup, pods could not be downloaded again.");
+        return;
+    }
+    let gitroot = git_root();
+    let lockfile_path = Path::new(&gitroot).join("Podfile.lock");
+    let lockfile = load_lockfile(&lockfile_path).unwrap_or_else(|error| panic!("Could not read {}: {}", lockfile_path.display(), error));
+    let installed = lockfile.root_pods();
+
+    let targets: Vec<String> = if from_lockfile {
+        installed.clone()
+    } else {
+        names.iter().filter_map(|name| {
+            let root = name.split('/').next().unwrap_or(name);
+            let found = installed.iter().find(|pod| pod.eq_ignore_ascii_case(root)).cloned();
+            if found.is_none() {
+                warn!("{} is not in {}, skipping.", name, lockfile_path.display());
+            }
+            found
+        }).collect()
+    };
+    if targets.is_empty() {
+        warn!("No pods to clean, pass pod names or --from-lockfile.");
+        return;
+    }
+
+    for pod in &targets {
+        say!("Cleaning pod {}", pod);
+        pod_command()
+            .args(["cache", "clean", pod.as_str(), "--all"])
+            .reported_output()
+            .expect("failed to execute process");
+        retry::remove_dir_all(&Path::new(&gitroot).join("Pods").join(pod));
+    }
+
+    say!("Reinstalling {}...", targets.join(", "));
+    reinstall();
+}
+
+// Compares Podfile.lock with Pods/Manifest.lock and the Podfile checksum, running pod install on drift when fix is set.
+// Returns whether the sandbox was in sync before any repair.
+pub fn check(fix: bool) -> bool {
+    let root = Path::new(&git_root()).to_path_buf();
+    let lockfile_path = root.join("Podfile.lock");
+    let lockfile = load_lockfile(&lockfile_path).unwrap_or_else(|error| panic!("Could not read {}: {}", lockfile_path.display(), error));
+    let differences = match load_lockfile(&root.join("Pods").join("Manifest.lock")) {
+        Ok(manifest) => differences(&lockfile, &manifest, podfile_checksum(&root.join("Podfile")).as_deref()),
+        Err(_) => vec!["Pods/Manifest.lock is missing, pods have not been installed".to_string()],
+    };
+
+    if differences.is_empty() {
+        say!("Pods are in sync with Podfile.lock");
+    } else {
+        warn!("Pods are out of sync with Podfile.lock:");
+        for difference in &differences {
+            warn!("  {}", difference);
+        }
+    }
+    report::result("pods_check", json!({ "in_sync": differences.is_empty(), "differences": differences }));
+
+    if fix && !differences.is_empty() {
+        say!("Repairing pods...");
+        reinstall();
+    }
+    differences.is_empty()
+}
+
+fn differences(lockfile: &Lockfile, manifest: &Lockfile, podfile_checksum: Option<&str>) -> Vec<String> {
+    let mut differences = Vec::new();
+    if lockfile.podfile_checksum != manifest.podfile_checksum {
+        differences.push(format!(
+            "PODFILE CHECKSUM is {} in Podfile.lock but {} in Manifest.lock",
+            lockfile.podfile_checksum.as_deref().unwrap_or("missing"),
+            manifest.podfile_checksum.as_deref().unwrap_or("missing")
+        ));
+    }
+    if let (Some(actual), Some(locked)) = (podfile_checksum, lockfile.podfile_checksum.as_deref())
+        && actual != locked
+    {
+        differences.push("Podfile changed since Podfile.lock was written".to_string());
+    }
+
+    let installed: HashMap<&str, &str> = manifest.pods.iter().map(|(name, version)| (name.as_str(), version.as_str())).collect();
+    for (name, version) in &lockfile.pods {
+        match installed.get(name.as_str()) {
+            None => differences.push(format!("{} {} is not installed", name, version)),
+            Some(installed) if installed != version => differences.push(format!("{} is locked at {} but {} is installed", name, version, installed)),
+            _ => (),
+        }
+    }
+    for (name, version) in &manifest.pods {
+        if !lockfile.pods.iter().any(|(locked, _)| locked == name) {
+            differences.push(format!("{} {} is installed but not in Podfile.lock", name, version));
+        }
+    }
+
+    let mut spec_checksums: Vec<(&String, &String)> = lockfile.spec_checksums.iter().collect();
+    spec_checksums.sort();
+    for (name, checksum) in spec_checksums {
+        if let Some(installed) = manifest.spec_checksums.get(name)
+            && installed != checksum
+        {
+            differences.push(format!("{} spec checksum differs", name));
+        }
+    }
+    differences
+}
+
+// CocoaPods checksums the Podfile as the SHA1 of its contents
+fn podfile_checksum(path: &Path) -> Option<String> {
+    if !path.exists() {
+        return None;
+    }
+    let output = Command::new("shasum").arg(path).reported_output().ok()?;
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    stdout.split_whitespace().next().map(str::to_string)
+}
+
+// pod install reinstalls pods that are missing or out of date and leaves the others alone
+fn reinstall() {
+    let output = retry::network_output(pod_command().arg("install")).expect("failed to execute process");
+    say!("{}", String::from_utf8_lossy(&output.stdout));
+    if !output.status.success() {
+        panic!("pod install failed: {}", String::from_utf8_lossy(&output.stderr));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::fixture;
+
+    fn load_fixture(name: &str) -> Lockfile {
+        load_lockfile(&fixture(&format!("pods/{}", name))).expect("Could not read lockfile fixture")
+    }
+
+    #[test]
+    fn parses_pods_checksums_and_the_podfile_checksum() {
+        let lockfile = load_fixture("Podfile.lock");
+        let pods: Vec<(&str, &str)> = lockfile.pods.iter().map(|(name, version)| (name.as_str(), version.as_str())).collect();
+        assert_eq!(pods, [
+            ("Alamofire", "5.9.1"),
+            ("Firebase/CoreOnly", "10.24.0"),
+            ("Firebase/Crashlytics", "10.24.0"),
+            ("FirebaseCore", "10.24.0"),
+            ("FirebaseCrashlytics", "10.24.0"),
+            ("SnapKit", "5.7.1"),
+        ]);
+        assert_eq!(lockfile.root_pods(), ["Alamofire", "Firebase", "FirebaseCore", "FirebaseCrashlytics", "SnapKit"]);
+        assert_eq!(lockfile.spec_checksums.len(), 5);
+        assert_eq!(lockfile.spec_checksums["FirebaseCrashlytics"], "af38ea4adfa606f6e63fcc22091b61e7938fcf66");
+        assert_eq!(lockfile.podfile_checksum.as_deref(), Some("7b2c46b4d0ad2b6b5b3d1bb2dd0bd0a1f2a0a6b4"));
+    }
+
+    #[test]
+    fn identical_lockfiles_do_not_differ() {
+        let lockfile = load_fixture("Podfile.lock");
+        assert!(differences(&lockfile, &load_fixture("Podfile.lock"), lockfile.podfile_checksum.as_deref()).is_empty());
+        assert!(differences(&lockfile, &load_fixture("Podfile.lock"), None).is_empty());
+    }
+
+    #[test]
+    fn lists_every_difference() {
+        assert_eq!(differences(&load_fixture("Podfile.lock"), &load_fixture("Manifest.lock"), Some("1f0e2d3c")), [
+            "PODFILE CHECKSUM is 7b2c46b4d0ad2b6b5b3d1bb2dd0bd0a1f2a0a6b4 in Podfile.lock but 0d3b1a8f2f5c6e1d1b3c5c7a7e3f1c2d4b6a8e0f in Manifest.lock",
+            "Podfile changed since Podfile.lock was written",
+            "Alamofire is locked at 5.9.1 but 5.8.0 is installed",
+            "Firebase/Crashlytics 10.24.0 is not installed",
+            "FirebaseCrashlytics 10.24.0 is not installed",
+            "Kingfisher 7.11.0 is installed but not in Podfile.lock",
+            "Alamofire spec checksum differs",
+        ]);
+    }
+}
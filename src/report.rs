@@ -0,0 +1,199 @@
+use std::any::Any;
+use std::fs;
+use std::io;
+use std::panic;
+use std::path::Path;
+use std::process::{self, Command, ExitStatus, Output};
+use std::sync::{Mutex, OnceLock};
+use std::time::Instant;
+
+use serde_json::{json, Map, Value};
+use walkdir::WalkDir;
+
+use crate::{has_flag, hooks};
+
+// Everything deleted so far, handed to hooks
+static DELETED: Mutex<Vec<String>> = Mutex::new(Vec::new());
+
+// Included in every event. Bumped whenever an event's fields change incompatibly.
+pub const SCHEMA_VERSION: u32 = 1;
+
+// Prints a line of human readable output, or a message event with --json
+macro_rules! say {
+    ($($arg:tt)*) => {
+        $crate::report::message(format!($($arg)*))
+    };
+}
+
+// Like say!, for problems that don't fail the command
+macro_rules! warn {
+    ($($arg:tt)*) => {
+        $crate::report::warning(format!($($arg)*))
+    };
+}
+
+pub fn json_enabled() -> bool {
+    static ENABLED: OnceLock<bool> = OnceLock::new();
+    *ENABLED.get_or_init(|| has_flag("json"))
+}
+
+// Prints a single event as one line of JSON
+pub fn emit(event: &str, fields: Value) {
+    let mut object = Map::new();
+    object.insert("schema_version".to_string(), json!(SCHEMA_VERSION));
+    object.insert("event".to_string(), json!(event));
+    if let Value::Object(fields) = fields {
+        object.extend(fields);
+    }
+    println!("{}", Value::Object(object));
+}
+
+pub fn message(text: String) {
+    if json_enabled() {
+        emit("message", json!({ "text": text }));
+    } else {
+        println!("{}", text);
+    }
+}
+
+pub fn warning(text: String) {
+    if json_enabled() {
+        emit("warning", json!({ "message": text }));
+    } else {
+        println!("{}", text);
+    }
+}
+
+// Marks the start of a step such as a package install, with its human readable announcement
+pub fn step(name: &str, text: &str) {
+    if json_enabled() {
+        emit("step", json!({ "name": name }));
+    } else {
+        println!("{}", text);
+    }
+}
+
+// Structured results, e.g. a build summary. Human output is printed separately by the caller.
+pub fn result(kind: &str, data: Value) {
+    if json_enabled() {
+        emit("result", json!({ "kind": kind, "data": data }));
+    }
+}
+
+pub fn remove_dir_all(path: &Path) -> io::Result<()> {
+    // Sizing a directory means walking it, so only do it when someone reads the number
+    let bytes = if json_enabled() { dir_size(path) } else { 0 };
+    fs::remove_dir_all(path)?;
+    record_deleted(path);
+    if json_enabled() {
+        emit("deleted", json!({ "path": path.display().to_string(), "bytes": bytes }));
+    }
+    Ok(())
+}
+
+pub fn remove_file(path: &Path) -> io::Result<()> {
+    let bytes = if json_enabled() { fs::metadata(path).map(|metadata| metadata.len()).unwrap_or(0) } else { 0 };
+    fs::remove_file(path)?;
+    record_deleted(path);
+    if json_enabled() {
+        emit("deleted", json!({ "path": path.display().to_string(), "bytes": bytes }));
+    }
+    Ok(())
+}
+
+fn record_deleted(path: &Path) {
+    DELETED.lock().expect("Deleted paths lock poisoned").push(path.display().to_string());
+}
+
+pub fn deleted_paths() -> Vec<String> {
+    DELETED.lock().expect("Deleted paths lock poisoned").clone()
+}
+
+pub fn dir_size(path: &Path) -> u64 {
+    WalkDir::new(path)
+        .into_iter()
+        .flatten()
+        .filter_map(|entry| entry.metadata().ok())
+        .filter(|metadata| metadata.is_file())
+        .map(|metadata| metadata.len())
+        .sum()
+}
+
+pub fn format_size(bytes: u64) -> String {
+    let units = ["B", "KB", "MB", "GB", "TB"];
+    let mut size = bytes as f64;
+    let mut unit = 0;
+    while size >= 1024.0 && unit < units.len() - 1 {
+        size /= 1024.0;
+        unit += 1;
+    }
+    if unit == 0 {
+        format!("{} {}", bytes, units[0])
+    } else {
+        format!("{:.1} {}", size, units[unit])
+    }
+}
+
+pub fn subprocess(command: &Command, status: ExitStatus) {
+    if json_enabled() {
+        let args: Vec<String> = command.get_args().map(|arg| arg.to_string_lossy().into_owned()).collect();
+        emit("subprocess", json!({
+            "program": command.get_program().to_string_lossy(),
+            "args": args,
+            "exit_code": status.code(),
+        }));
+    }
+}
+
+pub trait ReportedCommand {
+    // Command::output, reporting the exit code
+    fn reported_output(&mut self) -> io::Result<Output>;
+}
+
+impl ReportedCommand for Command {
+    fn reported_output(&mut self) -> io::Result<Output> {
+        let output = self.output()?;
+        subprocess(self, output.status);
+        Ok(output)
+    }
+}
+
+// Runs a CLI command between its pre_ and post_ hooks, reporting its start and final status. Commands fail
+// by panicking, so the panic is caught here to report the failure before exiting non-zero.
+pub fn run_command(name: &str, hook: &str, command: fn()) {
+    let started = Instant::now();
+    // The panic message is meant for the user, Rust's report of where it happened isn't
+    panic::set_hook(Box::new(|info| eprintln!("Error: {}", panic_message(info.payload()))));
+    if json_enabled() {
+        emit("command_started", json!({ "command": name }));
+    }
+    let result = panic::catch_unwind(|| {
+        hooks::run(&format!("pre_{}", hook), name, None);
+        command();
+    });
+    // A failing post_ hook fails an otherwise successful command
+    let succeeded = result.is_ok();
+    if !succeeded {
+        // The command's error was printed already, post_ hooks failing for the same reason would repeat it
+        panic::set_hook(Box::new(|_| ()));
+    }
+    let result = result.and(panic::catch_unwind(|| hooks::run(&format!("post_{}", hook), name, Some(succeeded))));
+    if json_enabled() {
+        let error = result.as_ref().err().map(|payload| panic_message(payload.as_ref()));
+        emit("finished", json!({
+            "command": name,
+            "status": if result.is_ok() { "success" } else { "failure" },
+            "error": error,
+            "duration_ms": started.elapsed().as_millis() as u64,
+        }));
+    }
+    if result.is_err() {
+        process::exit(1);
+    }
+}
+
+fn panic_message(payload: &(dyn Any + Send)) -> String {
+    payload.downcast_ref::<String>().cloned()
+        .or_else(|| payload.downcast_ref::<&str>().map(|message| message.to_string()))
+        .unwrap_or_else(|| "unknown error".to_string())
+}
@@ -0,0 +1,238 @@
+use std::collections::HashMap;
+use std::io;
+use std::path::Path;
+use std::process::{Command, Output};
+use std::sync::OnceLock;
+use std::thread;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use serde::{Deserialize, Serialize};
+
+use crate::report::{self, ReportedCommand};
+use crate::{load_config, Config};
+
+#[derive(Clone, Copy)]
+pub enum StepKind {
+    Delete,
+    Network,
+}
+
+impl StepKind {
+    fn name(&self) -> &'static str {
+        match self {
+            StepKind::Delete => "delete",
+            StepKind::Network => "network",
+        }
+    }
+}
+
+// A [retry.delete] or [retry.network] section, unset values fall back to the step kind's defaults
+#[derive(Serialize, Deserialize, Clone, Debug, Default, PartialEq)]
+#[serde(default)]
+pub struct RetryOverrides {
+    attempts: Option<u32>,
+    backoff_ms: Option<u64>,
+    backoff_multiplier: Option<f64>,
+    max_backoff_ms: Option<u64>,
+    jitter_ms: Option<u64>,
+    retryable: Option<Vec<String>>,
+}
+
+pub struct RetryPolicy {
+    attempts: u32,
+    backoff_ms: u64,
+    backoff_multiplier: f64,
+    max_backoff_ms: u64,
+    jitter_ms: u64,
+    // Substrings of the error or command output that make a failure worth retrying, empty retries everything
+    retryable: Vec<String>,
+}
+
+impl RetryPolicy {
+    fn defaults(kind: StepKind) -> RetryPolicy {
+        match kind {
+            // Xcode and the indexer keep files in DerivedData open for a while after a build
+            StepKind::Delete => RetryPolicy {
+                attempts: 14,
+                backoff_ms: 999,
+                backoff_multiplier: 1.0,
+                max_backoff_ms: 30_000,
+                jitter_ms: 0,
+                retryable: Vec::new(),
+            },
+            StepKind::Network => RetryPolicy {
+                attempts: 3,
+                backoff_ms: 2_000,
+                backoff_multiplier: 2.0,
+                max_backoff_ms: 30_000,
+                jitter_ms: 1_000,
+                retryable: [
+                    "Could not resolve host",
+                    "timed out",
+                    "Connection reset",
+                    "Couldn't connect",
+                    "network connection was lost",
+                    "SSL",
+                    "HTTP 5",
+                    "returned error: 5",
+                    "failed to clone",
+                    "Failed to connect",
+                ].iter().map(|pattern| pattern.to_string()).collect(),
+            },
+        }
+    }
+
+    pub fn load(kind: StepKind) -> RetryPolicy {
+        static OVERRIDES: OnceLock<HashMap<String, RetryOverrides>> = OnceLock::new();
+        let overrides = OVERRIDES.get_or_init(|| {
+            let config: Config = load_config();
+            config.retry
+        });
+        RetryPolicy::with_overrides(kind, overrides.get(kind.name()).cloned())
+    }
+
+    fn with_overrides(kind: StepKind, overrides: Option<RetryOverrides>) -> RetryPolicy {
+        let defaults = RetryPolicy::defaults(kind);
+        let Some(overrides) = overrides else {
+            return defaults;
+        };
+        RetryPolicy {
+            attempts: overrides.attempts.unwrap_or(defaults.attempts).max(1),
+            backoff_ms: overrides.backoff_ms.unwrap_or(defaults.backoff_ms),
+            backoff_multiplier: overrides.backoff_multiplier.unwrap_or(defaults.backoff_multiplier),
+            max_backoff_ms: overrides.max_backoff_ms.unwrap_or(defaults.max_backoff_ms),
+            jitter_ms: overrides.jitter_ms.unwrap_or(defaults.jitter_ms),
+            retryable: overrides.retryable.unwrap_or(defaults.retryable),
+        }
+    }
+
+    fn is_retryable(&self, error: &str) -> bool {
+        self.retryable.is_empty() || self.retryable.iter().any(|pattern| error.contains(pattern.as_str()))
+    }
+
+    // Exponential backoff from the first retry, capped, plus up to jitter_ms of noise so
+    // parallel runs don't retry in lockstep
+    fn delay(&self, attempt: u32) -> Duration {
+        let backoff = self.backoff_ms as f64 * self.backoff_multiplier.powi(attempt as i32 - 1);
+        let backoff = (backoff as u64).min(self.max_backoff_ms);
+        let jitter = if self.jitter_ms == 0 {
+            0
+        } else {
+            let nanos = SystemTime::now().duration_since(UNIX_EPOCH).map(|now| now.subsec_nanos()).unwrap_or(0);
+            nanos as u64 % (self.jitter_ms + 1)
+        };
+        Duration::from_millis(backoff + jitter)
+    }
+}
+
+// Removes a directory, retrying while it is locked. Returns whether the directory is gone.
+pub fn remove_dir_all(path: &Path) -> bool {
+    remove_with_retry(path, report::remove_dir_all)
+}
+
+pub fn remove_file(path: &Path) -> bool {
+    remove_with_retry(path, report::remove_file)
+}
+
+fn remove_with_retry(path: &Path, remove: fn(&Path) -> io::Result<()>) -> bool {
+    let policy = RetryPolicy::load(StepKind::Delete);
+    let mut attempt = 1;
+    loop {
+        match remove(path) {
+            Ok(_some) => return true,
+            Err(error) if error.kind() == io::ErrorKind::NotFound => return true,
+            Err(error) if attempt < policy.attempts && policy.is_retryable(&error.to_string()) => {
+                warn!("Error: {}. {} could be locked, retrying. Attempt {} of {}.", error, path.display(), attempt, policy.attempts);
+                thread::sleep(policy.delay(attempt));
+            }
+            Err(error) => {
+                warn!("Failed to remove {} after {} attempts: {}", path.display(), attempt, error);
+                return false;
+            }
+        }
+        attempt += 1;
+    }
+}
+
+// Runs a command that talks to the network, retrying failures whose output looks like a network problem
+pub fn network_output(command: &mut Command) -> io::Result<Output> {
+    network_output_with(command, &RetryPolicy::load(StepKind::Network))
+}
+
+fn network_output_with(command: &mut Command, policy: &RetryPolicy) -> io::Result<Output> {
+    let mut attempt = 1;
+    loop {
+        let output = command.reported_output()?;
+        if output.status.success() || attempt >= policy.attempts {
+            return Ok(output);
+        }
+        let text = String::from_utf8_lossy(&output.stdout).into_owned() + &String::from_utf8_lossy(&output.stderr);
+        if !policy.is_retryable(&text) {
+            return Ok(output);
+        }
+        let args: Vec<String> = command.get_args().map(|arg| arg.to_string_lossy().into_owned()).collect();
+        warn!("{} {} failed, retrying. Attempt {} of {}.", command.get_program().to_string_lossy(), args.join(" "), attempt, policy.attempts);
+        thread::sleep(policy.delay(attempt));
+        attempt += 1;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn policy(backoff_ms: u64, backoff_multiplier: f64, max_backoff_ms: u64, jitter_ms: u64) -> RetryPolicy {
+        RetryPolicy { attempts: 3, backoff_ms, backoff_multiplier, max_backoff_ms, jitter_ms, retryable: vec!["Could not resolve host".to_string()] }
+    }
+
+    #[test]
+    fn overrides_replace_only_the_values_they_set() {
+        let overrides = RetryOverrides { attempts: Some(5), jitter_ms: Some(0), ..RetryOverrides::default() };
+        let policy = RetryPolicy::with_overrides(StepKind::Network, Some(overrides));
+        let defaults = RetryPolicy::defaults(StepKind::Network);
+        assert_eq!((policy.attempts, policy.jitter_ms), (5, 0));
+        assert_eq!((policy.backoff_ms, policy.backoff_multiplier, policy.max_backoff_ms), (defaults.backoff_ms, defaults.backoff_multiplier, defaults.max_backoff_ms));
+        assert_eq!(policy.retryable, defaults.retryable);
+
+        let policy = RetryPolicy::with_overrides(StepKind::Delete, None);
+        assert_eq!((policy.attempts, policy.backoff_ms), (14, 999));
+        assert!(policy.is_retryable("Directory not empty"));
+    }
+
+    #[test]
+    fn at_least_one_attempt_is_made() {
+        let overrides = RetryOverrides { attempts: Some(0), ..RetryOverrides::default() };
+        assert_eq!(RetryPolicy::with_overrides(StepKind::Delete, Some(overrides)).attempts, 1);
+    }
+
+    #[test]
+    fn backoff_grows_by_the_multiplier_up_to_the_cap() {
+        let policy = policy(1_000, 2.0, 5_000, 0);
+        let delays: Vec<u128> = (1..=5).map(|attempt| policy.delay(attempt).as_millis()).collect();
+        assert_eq!(delays, [1_000, 2_000, 4_000, 5_000, 5_000]);
+    }
+
+    #[test]
+    fn jitter_stays_within_bounds() {
+        let policy = policy(100, 1.0, 30_000, 50);
+        for _ in 0..100 {
+            let delay = policy.delay(1).as_millis();
+            assert!((100..=150).contains(&delay), "{}", delay);
+        }
+    }
+
+    #[test]
+    fn retries_only_retryable_output() {
+        let dir = std::env::temp_dir().join(format!("sass-retry-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let policy = policy(0, 1.0, 0, 0);
+        for (message, runs) in [("Could not resolve host: github.com", 3), ("Permission denied", 1)] {
+            let counter = dir.join("runs");
+            let _ = std::fs::remove_file(&counter);
+            let script = format!("echo run >> '{}'; echo '{}' >&2; exit 1", counter.display(), message);
+            let output = network_output_with(Command::new("sh").args(["-c", &script]), &policy).unwrap();
+            assert!(!output.status.success());
+            assert_eq!(std::fs::read_to_string(&counter).unwrap().lines().count(), runs, "{}", message);
+        }
+    }
+}
@@ -0,0 +1,562 @@
+use std::process::Command;
+
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+use crate::report::{format_size, ReportedCommand};
+use crate::{arg_value, has_flag, load_config, operands, Config};
+
+// A [[simulators]] entry
+#[derive(Serialize, Deserialize, Clone, Debug, Default, PartialEq)]
+#[serde(default)]
+pub struct SimulatorDevice {
+    // Defaults to the device type's name
+    pub name: Option<String>,
+    // A name or identifier, e.g. "iPhone 15" or com.apple.CoreSimulator.SimDeviceType.iPhone-15
+    pub device_type: String,
+    // e.g. "iOS 17.5", or "iOS 18" for the newest installed 18.x
+    pub runtime: String,
+}
+
+impl SimulatorDevice {
+    pub fn display_name(&self) -> &str {
+        self.name.as_deref().unwrap_or(&self.device_type)
+    }
+}
+
+// A device as `simctl list -j` reports it
+struct Device {
+    udid: String,
+    name: String,
+    device_type: String,
+    runtime: String,
+}
+
+#[derive(Debug, PartialEq)]
+enum Change {
+    Create { name: String, device_type: String, runtime: String },
+    Rename { udid: String, from: String, to: String },
+    Delete { udid: String, name: String },
+}
+
+struct Runtime {
+    identifier: String,
+    // iOS, tvOS, watchOS, xrOS
+    platform: String,
+    version: String,
+    build: String,
+    bytes: u64,
+    deletable: bool,
+}
+
+impl Runtime {
+    fn major(&self) -> u32 {
+        self.version.split('.').next().and_then(|major| major.parse().ok()).unwrap_or(0)
+    }
+
+    fn name(&self) -> String {
+        format!("{} {}", self.platform, self.version)
+    }
+
+    // Keep-list entries name a platform and a version prefix, e.g. "iOS 17" keeps every iOS 17.x
+    fn matches(&self, entry: &str) -> bool {
+        let Some((platform, version)) = entry.trim().split_once(' ') else {
+            return false;
+        };
+        let version = version.trim();
+        platform.eq_ignore_ascii_case(&self.platform)
+            && (self.version == version || self.version.starts_with(&format!("{}.", version)))
+    }
+}
+
+pub fn sim() {
+    let args = operands("sim", "si");
+    match args.first().map(String::as_str) {
+        None => list_runtimes(),
+        Some("runtimes") => match args.get(1).map(String::as_str) {
+            None | Some("list") => list_runtimes(),
+            Some("prune") => prune_runtimes(has_flag("dry-run")),
+            Some(action) => warn!("Unknown sim runtimes action {}. Expected list or prune.", action),
+        },
+        Some("sync") => sync(has_flag("delete-undeclared"), has_flag("dry-run")),
+        Some("erase") => erase(has_flag("all")),
+        Some("boot") => boot(),
+        Some("push") => match &args[1..] {
+            [bundle_id, payload] => simctl(&["push", &default_device(), bundle_id, payload]),
+            _ => warn!("Pass a bundle id and a payload file, e.g. sass --sim push com.example.app push.json"),
+        },
+        Some("openurl") => match &args[1..] {
+            [url] => simctl(&["openurl", &default_device(), url]),
+            _ => warn!("Pass the URL to open, e.g. sass --sim openurl myapp://settings"),
+        },
+        Some("privacy") => match &args[1..] {
+            [action, service, bundle_id @ ..] if ["grant", "revoke", "reset"].contains(&action.as_str()) && bundle_id.len() <= 1 => {
+                let device = default_device();
+                let mut privacy_args = vec!["privacy", device.as_str(), action, service];
+                privacy_args.extend(bundle_id.iter().map(String::as_str));
+                simctl(&privacy_args);
+            }
+            _ => warn!("Pass grant, revoke or reset, a service and a bundle id, e.g. sass --sim privacy grant photos com.example.app"),
+        },
+        Some(action) => warn!("Unknown sim action {}. Expected runtimes [list|prune], sync, erase, boot, push, openurl or privacy.", action),
+    }
+}
+
+fn list_runtimes() {
+    let runtimes = runtimes();
+    if runtimes.is_empty() {
+        say!("No simulator runtimes installed.");
+        return;
+    }
+    let width = runtimes.iter().map(|runtime| runtime.name().len()).max().unwrap_or(0);
+    for runtime in &runtimes {
+        let deletable = if runtime.deletable { "" } else { "  (bundled with Xcode)" };
+        say!("{:width$}  {:10}  {:>9}  {}{}", runtime.name(), runtime.build, format_size(runtime.bytes), runtime.identifier, deletable, width = width);
+    }
+    let total: u64 = runtimes.iter().map(|runtime| runtime.bytes).sum();
+    say!("{} runtimes, {} total", runtimes.len(), format_size(total));
+}
+
+// Deletes runtimes older than the newest N major versions of their platform (--keep-majors N, or
+// simulator_runtime_majors), and those not on simulator_runtimes_keep. Either one keeps a runtime.
+fn prune_runtimes(dry_run: bool) {
+    let config: Config = load_config();
+    let keep_majors = arg_value("keep-majors").and_then(|majors| majors.parse::<usize>().ok()).or(config.simulator_runtime_majors);
+    let keep_list = &config.simulator_runtimes_keep;
+    if keep_majors.is_none() && keep_list.is_empty() {
+        warn!("Nothing to prune by, pass --keep-majors N or set simulator_runtimes_keep in the config.");
+        return;
+    }
+
+    let runtimes = runtimes();
+    let mut freed = 0;
+    for runtime in prunable(&runtimes, keep_majors, keep_list) {
+        if dry_run {
+            say!("Would delete {} ({})", runtime.name(), format_size(runtime.bytes));
+            freed += runtime.bytes;
+            continue;
+        }
+        say!("Deleting {} ({})", runtime.name(), format_size(runtime.bytes));
+        let output = Command::new("xcrun")
+            .args(["simctl", "runtime", "delete", runtime.identifier.as_str()])
+            .reported_output()
+            .expect("failed to execute process");
+        if output.status.success() {
+            freed += runtime.bytes;
+        } else {
+            warn!("Error deleting {}: {}", runtime.name(), String::from_utf8_lossy(&output.stderr).trim());
+        }
+    }
+    if dry_run {
+        say!("Would reclaim {}", format_size(freed));
+    } else {
+        say!("Reclaimed {}", format_size(freed));
+    }
+}
+
+// The runtimes neither setting keeps, leaving those bundled with Xcode alone
+fn prunable<'a>(runtimes: &'a [Runtime], keep_majors: Option<usize>, keep_list: &[String]) -> Vec<&'a Runtime> {
+    runtimes.iter().filter(|runtime| {
+        let recent = keep_majors.is_some_and(|keep_majors| {
+            let mut majors: Vec<u32> = runtimes.iter().filter(|other| other.platform == runtime.platform).map(Runtime::major).collect();
+            majors.sort_unstable_by(|a, b| b.cmp(a));
+            majors.dedup();
+            majors.iter().take(keep_majors).any(|major| *major == runtime.major())
+        });
+        let listed = keep_list.iter().any(|entry| runtime.matches(entry));
+        !recent && !listed && runtime.deletable
+    }).collect()
+}
+
+// Installed runtimes from `simctl runtime list -j`, newest first per platform
+fn runtimes() -> Vec<Runtime> {
+    let output = Command::new("xcrun")
+        .args(["simctl", "runtime", "list", "-j"])
+        .reported_output()
+        .expect("failed to execute process");
+    if !output.status.success() {
+        panic!("Could not list simulator runtimes: {}", String::from_utf8_lossy(&output.stderr));
+    }
+    let json: Value = serde_json::from_slice(&output.stdout).expect("Could not parse simctl runtime list");
+    parse_runtimes(&json)
+}
+
+fn parse_runtimes(json: &Value) -> Vec<Runtime> {
+    let Some(entries) = json.as_object() else {
+        return Vec::new();
+    };
+    let mut runtimes: Vec<Runtime> = entries.iter().map(|(identifier, entry)| {
+        let field = |name: &str| entry[name].as_str().unwrap_or_default().to_string();
+        // e.g. com.apple.CoreSimulator.SimRuntime.iOS-17-5
+        let runtime_identifier = field("runtimeIdentifier");
+        let platform = runtime_identifier.rsplit('.').next().and_then(|name| name.split('-').next()).unwrap_or_default().to_string();
+        Runtime {
+            identifier: identifier.clone(),
+            platform,
+            version: field("version"),
+            build: field("build"),
+            bytes: entry["sizeBytes"].as_u64().unwrap_or(0),
+            deletable: entry["deletable"].as_bool().unwrap_or(true),
+        }
+    }).collect();
+    runtimes.sort_by(|a, b| a.platform.cmp(&b.platform).then_with(|| version_parts(&b.version).cmp(&version_parts(&a.version))));
+    runtimes
+}
+
+fn version_parts(version: &str) -> Vec<u32> {
+    version.split('.').map(|part| part.parse().unwrap_or(0)).collect()
+}
+
+// Brings the installed devices in line with [[simulators]]: creates missing ones, renames drifted ones,
+// and with --delete-undeclared deletes every other device. Prints the plan before applying it.
+fn sync(delete_undeclared: bool, dry_run: bool) {
+    let config: Config = load_config();
+    if config.simulators.is_empty() {
+        warn!("No simulators declared, add [[simulators]] entries to the config.");
+        return;
+    }
+    let list = simctl_list();
+    let plan = plan(&list, &config.simulators, delete_undeclared);
+    if plan.is_empty() {
+        say!("Simulators are in sync with the config.");
+        return;
+    }
+    say!("Plan:");
+    for change in &plan {
+        match change {
+            Change::Create { name, runtime, .. } => say!("  create {} ({})", name, runtime_name(&list, runtime)),
+            Change::Rename { udid, from, to } => say!("  rename {} to {} ({})", from, to, udid),
+            Change::Delete { udid, name } => say!("  delete {} ({})", name, udid),
+        }
+    }
+    if dry_run {
+        return;
+    }
+
+    for change in &plan {
+        let args = match change {
+            Change::Create { name, device_type, runtime } => vec!["create", name, device_type, runtime],
+            Change::Rename { udid, to, .. } => vec!["rename", udid, to],
+            Change::Delete { udid, .. } => vec!["delete", udid],
+        };
+        let output = Command::new("xcrun")
+            .arg("simctl")
+            .args(&args)
+            .reported_output()
+            .expect("failed to execute process");
+        if !output.status.success() {
+            panic!("simctl {} failed: {}", args.join(" "), String::from_utf8_lossy(&output.stderr).trim());
+        }
+    }
+    say!("Applied {} changes.", plan.len());
+}
+
+// The changes that bring the devices in `simctl list` in line with the declared simulators
+fn plan(list: &Value, simulators: &[SimulatorDevice], delete_undeclared: bool) -> Vec<Change> {
+    let devices = devices(list);
+    let mut claimed: Vec<&str> = Vec::new();
+    let mut wanted: Vec<(&SimulatorDevice, String, String)> = Vec::new();
+    for declared in simulators {
+        let Some(device_type) = resolve_device_type(list, &declared.device_type) else {
+            warn!("Unknown device type {}, skipping {}.", declared.device_type, declared.display_name());
+            continue;
+        };
+        let Some(runtime) = resolve_runtime(list, &declared.runtime) else {
+            warn!("Runtime {} is not installed, skipping {}.", declared.runtime, declared.display_name());
+            continue;
+        };
+        // Devices that already match are claimed first, so a rename never takes one of them
+        if let Some(device) = devices.iter().find(|device| {
+            device.name == declared.display_name() && device.device_type == device_type && device.runtime == runtime && !claimed.contains(&device.udid.as_str())
+        }) {
+            claimed.push(&device.udid);
+        } else {
+            wanted.push((declared, device_type, runtime));
+        }
+    }
+
+    let mut plan = Vec::new();
+    for (declared, device_type, runtime) in wanted {
+        let drifted = devices.iter().find(|device| {
+            device.device_type == device_type && device.runtime == runtime && !claimed.contains(&device.udid.as_str())
+        });
+        match drifted {
+            Some(device) => {
+                claimed.push(&device.udid);
+                plan.push(Change::Rename { udid: device.udid.clone(), from: device.name.clone(), to: declared.display_name().to_string() });
+            }
+            None => plan.push(Change::Create { name: declared.display_name().to_string(), device_type, runtime }),
+        }
+    }
+    if delete_undeclared {
+        for device in devices.iter().filter(|device| !claimed.contains(&device.udid.as_str())) {
+            plan.push(Change::Delete { udid: device.udid.clone(), name: device.name.clone() });
+        }
+    }
+    plan
+}
+
+fn simctl_list() -> Value {
+    let output = Command::new("xcrun")
+        .args(["simctl", "list", "-j", "devices", "devicetypes", "runtimes"])
+        .reported_output()
+        .expect("failed to execute process");
+    if !output.status.success() {
+        panic!("Could not list simulators: {}", String::from_utf8_lossy(&output.stderr));
+    }
+    serde_json::from_slice(&output.stdout).expect("Could not parse simctl list")
+}
+
+// Available devices, i.e. those whose runtime is still installed
+fn devices(list: &Value) -> Vec<Device> {
+    let Some(runtimes) = list["devices"].as_object() else {
+        return Vec::new();
+    };
+    runtimes.iter().flat_map(|(runtime, devices)| {
+        devices.as_array().into_iter().flatten()
+            .filter(|device| device["isAvailable"].as_bool().unwrap_or(true))
+            .map(move |device| Device {
+                udid: device["udid"].as_str().unwrap_or_default().to_string(),
+                name: device["name"].as_str().unwrap_or_default().to_string(),
+                device_type: device["deviceTypeIdentifier"].as_str().unwrap_or_default().to_string(),
+                runtime: runtime.clone(),
+            })
+    }).collect()
+}
+
+fn resolve_device_type(list: &Value, wanted: &str) -> Option<String> {
+    list["devicetypes"].as_array()?.iter()
+        .find(|device_type| device_type["name"].as_str() == Some(wanted) || device_type["identifier"].as_str() == Some(wanted))
+        .and_then(|device_type| device_type["identifier"].as_str())
+        .map(str::to_string)
+}
+
+// An exact name or identifier, otherwise the newest runtime whose version starts with the given one
+fn resolve_runtime(list: &Value, wanted: &str) -> Option<String> {
+    let runtimes: Vec<&Value> = list["runtimes"].as_array()?.iter().filter(|runtime| runtime["isAvailable"].as_bool().unwrap_or(true)).collect();
+    if let Some(runtime) = runtimes.iter().find(|runtime| runtime["name"].as_str() == Some(wanted) || runtime["identifier"].as_str() == Some(wanted)) {
+        return runtime["identifier"].as_str().map(str::to_string);
+    }
+    let prefix = format!("{}.", wanted);
+    runtimes.iter()
+        .filter(|runtime| runtime["name"].as_str().is_some_and(|name| name.starts_with(&prefix)))
+        .max_by_key(|runtime| version_parts(runtime["version"].as_str().unwrap_or_default()))
+        .and_then(|runtime| runtime["identifier"].as_str())
+        .map(str::to_string)
+}
+
+fn runtime_name(list: &Value, identifier: &str) -> String {
+    list["runtimes"].as_array().into_iter().flatten()
+        .find(|runtime| runtime["identifier"].as_str() == Some(identifier))
+        .and_then(|runtime| runtime["name"].as_str())
+        .unwrap_or(identifier)
+        .to_string()
+}
+
+// Erases the default device, or with --all every device declared in [[simulators]]
+fn erase(all: bool) {
+    let udids = if all {
+        let config: Config = load_config();
+        declared_devices(&simctl_list(), &config.simulators)
+    } else {
+        vec![default_device()]
+    };
+    for udid in udids {
+        say!("Erasing {}", udid);
+        // Booted devices can't be erased
+        Command::new("xcrun")
+            .args(["simctl", "shutdown", udid.as_str()])
+            .reported_output()
+            .expect("failed to execute process");
+        simctl(&["erase", &udid]);
+    }
+}
+
+// The UDIDs of the installed devices matching [[simulators]] entries by name and runtime
+fn declared_devices(list: &Value, simulators: &[SimulatorDevice]) -> Vec<String> {
+    let devices = devices(list);
+    simulators.iter()
+        .filter_map(|declared| {
+            let runtime = resolve_runtime(list, &declared.runtime);
+            devices.iter().find(|device| device.name == declared.display_name() && Some(&device.runtime) == runtime.as_ref())
+        })
+        .map(|device| device.udid.clone())
+        .collect()
+}
+
+// Boots the default device and waits until it has finished booting
+fn boot() {
+    let udid = default_device();
+    say!("Booting {}...", udid);
+    simctl(&["bootstatus", &udid, "-b"]);
+    say!("{} is ready.", udid);
+}
+
+fn simctl(args: &[&str]) {
+    let output = Command::new("xcrun")
+        .arg("simctl")
+        .args(args)
+        .reported_output()
+        .expect("failed to execute process");
+    if !output.status.success() {
+        panic!("simctl {} failed: {}", args.join(" "), String::from_utf8_lossy(&output.stderr).trim());
+    }
+}
+
+// The UDID of --device, default_simulator or the first [[simulators]] entry, each a name or UDID
+fn default_device() -> String {
+    let config: Config = load_config();
+    let wanted = arg_value("device")
+        .or(config.default_simulator.clone())
+        .or(config.simulators.first().map(|declared| declared.display_name().to_string()))
+        .unwrap_or_else(|| panic!("No simulator selected, pass --device or set default_simulator in the config"));
+    let devices = devices(&simctl_list());
+    let device = find_device(&devices, &wanted).unwrap_or_else(|| panic!("No simulator named {} found", wanted));
+    device.udid.clone()
+}
+
+// The device with the given UDID or name, the first one listed when several share the name
+fn find_device<'a>(devices: &'a [Device], wanted: &str) -> Option<&'a Device> {
+    let mut matches = devices.iter().filter(|device| device.udid == wanted || device.name == wanted);
+    let device = matches.next()?;
+    if matches.next().is_some() {
+        warn!("Several simulators are named {}, using {}. Pass its UDID to pick another.", wanted, device.udid);
+    }
+    Some(device)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::json_fixture;
+
+    fn names(runtimes: Vec<&Runtime>) -> Vec<String> {
+        runtimes.into_iter().map(Runtime::name).collect()
+    }
+
+    fn declared(name: Option<&str>, device_type: &str, runtime: &str) -> SimulatorDevice {
+        SimulatorDevice { name: name.map(str::to_string), device_type: device_type.to_string(), runtime: runtime.to_string() }
+    }
+
+    #[test]
+    fn resolves_runtimes_by_name_identifier_or_newest_minor() {
+        let list = json_fixture("simctl/list.json");
+        assert_eq!(resolve_runtime(&list, "iOS 18").as_deref(), Some("com.apple.CoreSimulator.SimRuntime.iOS-18-2"));
+        assert_eq!(resolve_runtime(&list, "iOS 18.0").as_deref(), Some("com.apple.CoreSimulator.SimRuntime.iOS-18-0"));
+        assert_eq!(resolve_runtime(&list, "com.apple.CoreSimulator.SimRuntime.iOS-17-5").as_deref(), Some("com.apple.CoreSimulator.SimRuntime.iOS-17-5"));
+        assert_eq!(resolve_runtime(&list, "iOS 1"), None);
+        assert_eq!(resolve_device_type(&list, "iPhone 16 Pro").as_deref(), Some("com.apple.CoreSimulator.SimDeviceType.iPhone-16-Pro"));
+        assert_eq!(resolve_device_type(&list, "Apple Watch Ultra"), None);
+    }
+
+    #[test]
+    fn finds_devices_by_udid_or_name() {
+        let devices = devices(&json_fixture("simctl/list.json"));
+        let udid = |wanted: &str| find_device(&devices, wanted).map(|device| device.udid.as_str());
+        assert_eq!(udid("UI Tests iPhone"), Some("1C2D3E4F-5A6B-4C7D-8E9F-0A1B2C3D4E5F"));
+        assert_eq!(udid("5A6B7C8D-9E0F-4A1B-8C2D-3E4F5A6B7C8D"), Some("5A6B7C8D-9E0F-4A1B-8C2D-3E4F5A6B7C8D"));
+        // Both iOS 18.2 iPhones are named "iPhone 15"
+        assert_eq!(udid("iPhone 15"), Some("4F5A6B7C-8D9E-4F0A-9B2C-3D4E5F6A7B8C"));
+        assert_eq!(udid("Old iPhone"), None);
+        assert_eq!(udid("Nope"), None);
+    }
+
+    #[test]
+    fn finds_declared_devices_by_name_and_runtime() {
+        let simulators = [
+            declared(Some("UI Tests iPhone"), "iPhone 15", "iOS 17.5"),
+            declared(None, "iPad Pro 13-inch (M4)", "iOS 18"),
+            declared(None, "iPhone 15", "iOS 18"),
+            declared(Some("UI Tests iPhone"), "iPhone 15", "iOS 18"),
+        ];
+        assert_eq!(declared_devices(&json_fixture("simctl/list.json"), &simulators), ["1C2D3E4F-5A6B-4C7D-8E9F-0A1B2C3D4E5F", "4F5A6B7C-8D9E-4F0A-9B2C-3D4E5F6A7B8C"]);
+    }
+
+    #[test]
+    fn skips_unavailable_devices() {
+        let devices = devices(&json_fixture("simctl/list.json"));
+        assert_eq!(devices.len(), 5);
+        assert!(!devices.iter().any(|device| device.name == "Old iPhone"));
+    }
+
+    #[test]
+    fn matching_devices_need_no_changes() {
+        let simulators = [declared(Some("UI Tests iPhone"), "iPhone 15", "iOS 17.5"), declared(None, "iPhone 15", "iOS 18")];
+        assert!(plan(&json_fixture("simctl/list.json"), &simulators, false).is_empty());
+    }
+
+    #[test]
+    fn plans_creates_renames_and_deletes() {
+        let simulators = [
+            declared(Some("UI Tests iPhone"), "iPhone 15", "iOS 17.5"),
+            declared(Some("Renamed"), "iPhone 15", "iOS 18"),
+            declared(None, "iPhone 15", "iOS 18"),
+            declared(None, "iPad Pro 13-inch (M4)", "iOS 18.2"),
+            declared(Some("Second UI iPhone"), "com.apple.CoreSimulator.SimDeviceType.iPhone-15", "iOS 17.5"),
+            declared(Some("Watch"), "Apple Watch Ultra", "iOS 18"),
+            declared(Some("Future iPhone"), "iPhone 15", "iOS 19"),
+        ];
+        assert_eq!(plan(&json_fixture("simctl/list.json"), &simulators, true), [
+            // The exact "iPhone 15" match is claimed before the rename picks a device
+            Change::Rename { udid: "5A6B7C8D-9E0F-4A1B-8C2D-3E4F5A6B7C8D".to_string(), from: "iPhone 15".to_string(), to: "Renamed".to_string() },
+            Change::Rename { udid: "3E4F5A6B-7C8D-4E9F-8A1B-2C3D4E5F6A7B".to_string(), from: "My iPad".to_string(), to: "iPad Pro 13-inch (M4)".to_string() },
+            Change::Create {
+                name: "Second UI iPhone".to_string(),
+                device_type: "com.apple.CoreSimulator.SimDeviceType.iPhone-15".to_string(),
+                runtime: "com.apple.CoreSimulator.SimRuntime.iOS-17-5".to_string(),
+            },
+            Change::Delete { udid: "2D3E4F5A-6B7C-4D8E-9F0A-1B2C3D4E5F6A".to_string(), name: "iPhone 16 Pro".to_string() },
+        ]);
+    }
+
+    #[test]
+    fn keeps_undeclared_devices_unless_asked() {
+        let simulators = [declared(Some("UI Tests iPhone"), "iPhone 15", "iOS 17.5")];
+        assert!(plan(&json_fixture("simctl/list.json"), &simulators, false).is_empty());
+        assert_eq!(plan(&json_fixture("simctl/list.json"), &simulators, true).len(), 4);
+    }
+
+    #[test]
+    fn parses_runtimes_newest_first_per_platform() {
+        let runtimes = parse_runtimes(&json_fixture("simctl/runtimes.json"));
+        assert_eq!(names(runtimes.iter().collect()), ["iOS 18.2", "iOS 17.5", "iOS 17.0", "iOS 16.4", "watchOS 10.5", "watchOS 9.4"]);
+        assert_eq!(runtimes[1].identifier, "1B2C3D4E-2222-4F5A-9B0C-1D2E3F4A5B6C");
+        assert_eq!((runtimes[1].build.as_str(), runtimes[1].bytes), ("21F79", 7516192768));
+        assert!(!runtimes[0].deletable);
+    }
+
+    #[test]
+    fn keep_list_entries_match_by_platform_and_version_prefix() {
+        let runtimes = parse_runtimes(&json_fixture("simctl/runtimes.json"));
+        let ios_17_5 = &runtimes[1];
+        assert!(ios_17_5.matches("iOS 17"));
+        assert!(ios_17_5.matches("ios 17.5"));
+        assert!(ios_17_5.matches(" iOS  17.5 "));
+        assert!(!ios_17_5.matches("iOS 1"));
+        assert!(!ios_17_5.matches("iOS 17.0"));
+        assert!(!ios_17_5.matches("watchOS 17"));
+        assert!(!ios_17_5.matches("iOS"));
+    }
+
+    #[test]
+    fn prunes_runtimes_older_than_the_kept_majors() {
+        let runtimes = parse_runtimes(&json_fixture("simctl/runtimes.json"));
+        assert_eq!(names(prunable(&runtimes, Some(1), &[])), ["iOS 17.5", "iOS 17.0", "iOS 16.4", "watchOS 9.4"]);
+        assert_eq!(names(prunable(&runtimes, Some(2), &[])), ["iOS 16.4"]);
+    }
+
+    #[test]
+    fn prunes_runtimes_off_the_keep_list() {
+        let runtimes = parse_runtimes(&json_fixture("simctl/runtimes.json"));
+        let keep = ["iOS 17".to_string(), "watchOS 10".to_string()];
+        // iOS 18.2 is bundled with Xcode
+        assert_eq!(names(prunable(&runtimes, None, &keep)), ["iOS 16.4", "watchOS 9.4"]);
+    }
+
+    #[test]
+    fn either_setting_keeps_a_runtime() {
+        let runtimes = parse_runtimes(&json_fixture("simctl/runtimes.json"));
+        assert_eq!(names(prunable(&runtimes, Some(1), &["iOS 16.4".to_string()])), ["iOS 17.5", "iOS 17.0", "watchOS 9.4"]);
+        assert!(prunable(&runtimes, Some(2), &["iOS 16".to_string()]).is_empty());
+    }
+}
@@ -0,0 +1,232 @@
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use regex::Regex;
+use serde_json::Value;
+
+use crate::report::{self, format_size};
+use crate::{git_root, has_flag, operands, project_files, retry};
+
+const CACHE_ROOTS: [&str; 3] = ["~/Library/Caches/org.swift.swiftpm", "~/.cache/org.swift.swiftpm", "~/.swiftpm/cache"];
+
+#[derive(Clone, Copy, PartialEq)]
+enum EntryKind {
+    Repository,
+    Artifact,
+}
+
+struct CacheEntry {
+    kind: EntryKind,
+    // Package name for repositories, the cache folder name for artifacts
+    name: String,
+    path: PathBuf,
+    bytes: u64,
+}
+
+impl CacheEntry {
+    fn kind_name(&self) -> &'static str {
+        match self.kind {
+            EntryKind::Repository => "repository",
+            EntryKind::Artifact => "artifact",
+        }
+    }
+
+    // Artifact folders are named after their download URL, with `-` and `.` turned into `_`, which contains the
+    // package's name. Close enough to keep an artifact when pruning, not to pick one to remove.
+    fn is_referenced(&self, identities: &[String]) -> bool {
+        let name = self.name.to_lowercase();
+        match self.kind {
+            EntryKind::Repository => identities.contains(&name),
+            EntryKind::Artifact => identities.iter().any(|identity| name.contains(&identity.replace(['-', '.'], "_"))),
+        }
+    }
+
+    // Whether one of the lowercased names is exactly this entry's name as listed
+    fn is_named(&self, names: &[String]) -> bool {
+        names.contains(&self.name.to_lowercase())
+    }
+}
+
+pub fn spm_cache() {
+    let args = operands("spm-cache", "sc");
+    match args.first().map(String::as_str) {
+        None | Some("list") => list(),
+        Some("remove") => remove(&args[1..]),
+        Some("prune") => prune(has_flag("dry-run")),
+        Some(action) => warn!("Unknown spm-cache action {}. Expected list, remove <name...> or prune.", action),
+    }
+}
+
+fn list() {
+    let entries = cache_entries();
+    if entries.is_empty() {
+        say!("No SwiftPM caches found.");
+        return;
+    }
+    let identities = referenced_identities(Path::new(&git_root()));
+    let width = entries.iter().map(|entry| entry.name.len()).max().unwrap_or(0);
+    for entry in &entries {
+        let referenced = if entry.is_referenced(&identities) { "used" } else { "unused" };
+        say!("{:10}  {:width$}  {:>9}  {:6}  {}", entry.kind_name(), entry.name, format_size(entry.bytes), referenced, entry.path.display(), width = width);
+    }
+    let total: u64 = entries.iter().map(|entry| entry.bytes).sum();
+    say!("{} entries, {} total", entries.len(), format_size(total));
+}
+
+fn remove(names: &[String]) {
+    if names.is_empty() {
+        warn!("Pass the packages or artifacts to remove, e.g. sass --spm-cache remove swift-argument-parser");
+        return;
+    }
+    let names: Vec<String> = names.iter().map(|name| name.to_lowercase()).collect();
+    let entries: Vec<CacheEntry> = cache_entries().into_iter().filter(|entry| entry.is_named(&names)).collect();
+    for name in &names {
+        if !entries.iter().any(|entry| entry.name.to_lowercase() == *name) {
+            warn!("Nothing named {} in the SwiftPM caches, see sass --spm-cache list", name);
+        }
+    }
+    let mut freed = 0;
+    for entry in entries {
+        say!("Removing {} {} ({})", entry.kind_name(), entry.name, format_size(entry.bytes));
+        if retry::remove_dir_all(&entry.path) {
+            freed += entry.bytes;
+        }
+    }
+    say!("Freed {}", format_size(freed));
+}
+
+// Removes every cached repository and artifact no Package.resolved in the project refers to
+fn prune(dry_run: bool) {
+    let identities = referenced_identities(Path::new(&git_root()));
+    if identities.is_empty() {
+        warn!("No Package.resolved found in the project, refusing to prune the whole cache.");
+        return;
+    }
+    let mut freed = 0;
+    for entry in cache_entries().into_iter().filter(|entry| !entry.is_referenced(&identities)) {
+        if dry_run {
+            say!("Would remove {} {} ({})", entry.kind_name(), entry.name, format_size(entry.bytes));
+            freed += entry.bytes;
+        } else {
+            say!("Removing {} {} ({})", entry.kind_name(), entry.name, format_size(entry.bytes));
+            if retry::remove_dir_all(&entry.path) {
+                freed += entry.bytes;
+            }
+        }
+    }
+    if dry_run {
+        say!("Would free {}", format_size(freed));
+    } else {
+        say!("Freed {}", format_size(freed));
+    }
+}
+
+fn cache_entries() -> Vec<CacheEntry> {
+    // Repository mirrors are named <package>-<hash of the URL>
+    let hash_suffix = Regex::new(r"-[0-9a-f]{8,}$").expect("Cache hash regex failed to parse");
+    let mut entries = Vec::new();
+    for root in CACHE_ROOTS {
+        let root = PathBuf::from(shellexpand::tilde(root).into_owned());
+        for (subdir, kind) in [("repositories", EntryKind::Repository), ("artifacts", EntryKind::Artifact)] {
+            let Ok(dir_entries) = fs::read_dir(root.join(subdir)) else {
+                continue;
+            };
+            for dir_entry in dir_entries.flatten() {
+                let path = dir_entry.path();
+                if !path.is_dir() {
+                    continue;
+                }
+                let folder_name = dir_entry.file_name().to_string_lossy().into_owned();
+                let name = match kind {
+                    EntryKind::Repository => hash_suffix.replace(&folder_name, "").into_owned(),
+                    EntryKind::Artifact => folder_name,
+                };
+                entries.push(CacheEntry { kind, name, bytes: report::dir_size(&path), path });
+            }
+        }
+    }
+    entries.sort_by_key(|entry| std::cmp::Reverse(entry.bytes));
+    entries
+}
+
+// Lowercased package identities pinned by any Package.resolved in the project, including those of workspaces
+fn referenced_identities(root: &Path) -> Vec<String> {
+    let lockfiles = project_files(root).filter(|path| path.file_name().is_some_and(|name| name == "Package.resolved"));
+    let mut identities = Vec::new();
+    for lockfile in lockfiles {
+        for identity in pinned_identities(&lockfile) {
+            if !identities.contains(&identity) {
+                identities.push(identity);
+            }
+        }
+    }
+    identities
+}
+
+// Lowercased identities of the packages a Package.resolved pins, empty when it is missing
+pub fn pinned_identities(lockfile: &Path) -> Vec<String> {
+    let Ok(contents) = fs::read_to_string(lockfile) else {
+        return Vec::new();
+    };
+    let Ok(json) = serde_json::from_str::<Value>(&contents) else {
+        warn!("Could not parse {}", lockfile.display());
+        return Vec::new();
+    };
+    // Version 1 nests the pins under "object" and only has the repository URL
+    let pins = json.get("pins").or_else(|| json.get("object").and_then(|object| object.get("pins")));
+    pins.and_then(Value::as_array).into_iter().flatten().filter_map(|pin| {
+        let identity = pin.get("identity").and_then(Value::as_str).map(str::to_string).or_else(|| {
+            let url = pin.get("location").or_else(|| pin.get("repositoryURL")).and_then(Value::as_str)?;
+            let last_component = url.trim_end_matches('/').rsplit('/').next()?;
+            Some(last_component.trim_end_matches(".git").to_string())
+        });
+        identity.map(|identity| identity.to_lowercase())
+    }).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::fixture;
+
+    fn entry(kind: EntryKind, name: &str) -> CacheEntry {
+        CacheEntry { kind, name: name.to_string(), path: PathBuf::new(), bytes: 0 }
+    }
+
+    #[test]
+    fn removal_needs_the_exact_name() {
+        let names = ["swift".to_string()];
+        let artifact = entry(EntryKind::Artifact, "https___github_com_apple_swift_syntax_releases_download_600_0_0_SwiftSyntax_xcframework_zip");
+        assert!(!artifact.is_named(&names));
+        assert!(!entry(EntryKind::Repository, "swift-argument-parser").is_named(&names));
+        assert!(entry(EntryKind::Repository, "Swift").is_named(&names));
+        assert!(artifact.is_named(&[artifact.name.to_lowercase()]));
+    }
+
+    #[test]
+    fn artifacts_are_referenced_by_the_package_in_their_url() {
+        let artifact = entry(EntryKind::Artifact, "https___github_com_apple_swift_syntax_releases_download_600_0_0_SwiftSyntax_xcframework_zip");
+        assert!(artifact.is_referenced(&["swift-syntax".to_string()]));
+        assert!(!artifact.is_referenced(&["kingfisher".to_string()]));
+        let artifact = entry(EntryKind::Artifact, "https___dl_google_com_firebase_ios_sdk_11_0_0_FirebaseAnalytics_xcframework_zip");
+        assert!(artifact.is_referenced(&["firebase-ios-sdk".to_string()]));
+        assert!(!artifact.is_referenced(&["firebase-ios-sdk-extras".to_string()]));
+        assert!(entry(EntryKind::Repository, "Kingfisher").is_referenced(&["kingfisher".to_string()]));
+        assert!(!entry(EntryKind::Repository, "swift-argument-parser").is_referenced(&["swift".to_string()]));
+    }
+
+    #[test]
+    fn reads_identities_from_current_lockfiles() {
+        assert_eq!(pinned_identities(&fixture("spm/Package.resolved")), ["swift-argument-parser", "kingfisher"]);
+    }
+
+    #[test]
+    fn derives_identities_from_urls_in_version_1_lockfiles() {
+        assert_eq!(pinned_identities(&fixture("spm/Package.resolved.v1")), ["alamofire", "snapkit"]);
+    }
+
+    #[test]
+    fn missing_lockfiles_pin_nothing() {
+        assert!(pinned_identities(&fixture("spm/missing/Package.resolved")).is_empty());
+    }
+}
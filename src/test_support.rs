@@ -0,0 +1,13 @@
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use serde_json::Value;
+
+// Files under tests/fixtures, shared by the unit tests and, through a #[path] module, tests/cli.rs
+pub fn fixture(path: &str) -> PathBuf {
+    Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/fixtures").join(path)
+}
+
+pub fn json_fixture(path: &str) -> Value {
+    serde_json::from_str(&fs::read_to_string(fixture(path)).expect("Could not read fixture")).expect("Fixture is not valid JSON")
+}
@@ -0,0 +1,169 @@
+use std::collections::HashMap;
+use std::panic;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, SystemTime};
+use std::thread;
+
+use walkdir::{DirEntry, WalkDir};
+
+use crate::{git_root, install_packages, install_pods, load_config, rebuild_build_server, Config};
+
+const POLL_INTERVAL: Duration = Duration::from_millis(1000);
+// Changes are only acted on once the watched files have been quiet this long, so a git checkout
+// touching several manifests triggers a single round of steps
+const DEBOUNCE: Duration = Duration::from_millis(2000);
+const SKIPPED_DIRS: [&str; 6] = [".git", ".build", "Pods", "DerivedData", "node_modules", ".bundle"];
+
+#[derive(PartialEq, Eq, Hash, Clone, Copy, Debug)]
+enum Step {
+    InstallPackages,
+    InstallPods,
+    RebuildBuildServer,
+}
+
+impl Step {
+    fn run(&self) {
+        let step: fn() = match self {
+            Step::InstallPackages => install_packages,
+            Step::InstallPods => install_pods,
+            Step::RebuildBuildServer => rebuild_build_server,
+        };
+        // A failing step should not end the watch, the next change may fix it
+        if panic::catch_unwind(step).is_err() {
+            warn!("{:?} failed, waiting for further changes.", self);
+        }
+    }
+}
+
+pub fn watch() {
+    let config: Config = load_config();
+    let root = PathBuf::from(git_root());
+    let workspace_file = format!("{}.xcworkspace", config.workspace_name);
+    say!("Watching {} for manifest changes, press Ctrl-C to stop.", root.display());
+
+    let mut snapshot = scan(&root, &workspace_file);
+    let mut pending: Vec<PathBuf> = Vec::new();
+    let mut last_change = SystemTime::now();
+    loop {
+        thread::sleep(POLL_INTERVAL);
+        let current = scan(&root, &workspace_file);
+        let changed = changed_paths(&snapshot, &current);
+        snapshot = current;
+        if !changed.is_empty() {
+            for path in changed {
+                if !pending.contains(&path) {
+                    pending.push(path);
+                }
+            }
+            last_change = SystemTime::now();
+            continue;
+        }
+        if pending.is_empty() || last_change.elapsed().unwrap_or_default() < DEBOUNCE {
+            continue;
+        }
+
+        let mut steps: Vec<Step> = Vec::new();
+        for path in &pending {
+            say!("Changed: {}", path.display());
+            for step in steps_for(path, &workspace_file) {
+                if !steps.contains(&step) {
+                    steps.push(step);
+                }
+            }
+        }
+        // Dependency installs can change the build settings, so the build server always goes last
+        steps.sort_by_key(|step| *step == Step::RebuildBuildServer);
+        for step in steps {
+            step.run();
+        }
+        pending.clear();
+        // Ignore changes made by the steps themselves, e.g. an updated Package.resolved
+        snapshot = scan(&root, &workspace_file);
+    }
+}
+
+fn steps_for(path: &Path, workspace_file: &str) -> Vec<Step> {
+    let file_name = path.file_name().map(|name| name.to_string_lossy().into_owned()).unwrap_or_default();
+    match file_name.as_str() {
+        "Package.swift" | "Package.resolved" => vec![Step::InstallPackages, Step::RebuildBuildServer],
+        "Podfile" | "Podfile.lock" => vec![Step::InstallPods, Step::RebuildBuildServer],
+        "project.pbxproj" => vec![Step::RebuildBuildServer],
+        _ if path.components().any(|component| component.as_os_str() == workspace_file) => vec![Step::RebuildBuildServer],
+        _ => Vec::new(),
+    }
+}
+
+fn is_watched(entry: &DirEntry, workspace_file: &str) -> bool {
+    let file_name = entry.file_name().to_string_lossy();
+    match file_name.as_ref() {
+        "Package.swift" | "Package.resolved" | "Podfile" | "Podfile.lock" | "project.pbxproj" => true,
+        "contents.xcworkspacedata" => entry.path().parent().is_some_and(|parent| parent.ends_with(workspace_file)),
+        _ => false,
+    }
+}
+
+fn scan(root: &Path, workspace_file: &str) -> HashMap<PathBuf, SystemTime> {
+    let walker = WalkDir::new(root)
+        .into_iter()
+        .filter_entry(|entry| !(entry.file_type().is_dir() && SKIPPED_DIRS.contains(&entry.file_name().to_string_lossy().as_ref())));
+    let mut modified_times = HashMap::new();
+    for entry in walker {
+        let entry = match entry {
+            Ok(e) => e,
+            Err(_) => continue,
+        };
+        if !entry.file_type().is_file() || !is_watched(&entry, workspace_file) {
+            continue;
+        }
+        if let Ok(modified) = entry.path().metadata().and_then(|metadata| metadata.modified()) {
+            modified_times.insert(entry.into_path(), modified);
+        }
+    }
+    modified_times
+}
+
+fn changed_paths(before: &HashMap<PathBuf, SystemTime>, after: &HashMap<PathBuf, SystemTime>) -> Vec<PathBuf> {
+    let mut changed: Vec<PathBuf> = after.iter()
+        .filter(|(path, modified)| before.get(*path) != Some(*modified))
+        .map(|(path, _)| path.clone())
+        .collect();
+    changed.extend(before.keys().filter(|path| !after.contains_key(*path)).cloned());
+    changed
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn maps_manifests_to_steps() {
+        let workspace_file = "App.xcworkspace";
+        assert_eq!(steps_for(Path::new("/src/Package.swift"), workspace_file), [Step::InstallPackages, Step::RebuildBuildServer]);
+        assert_eq!(steps_for(Path::new("/src/App.xcworkspace/xcshareddata/swiftpm/Package.resolved"), workspace_file), [Step::InstallPackages, Step::RebuildBuildServer]);
+        assert_eq!(steps_for(Path::new("/src/Podfile.lock"), workspace_file), [Step::InstallPods, Step::RebuildBuildServer]);
+        assert_eq!(steps_for(Path::new("/src/App.xcodeproj/project.pbxproj"), workspace_file), [Step::RebuildBuildServer]);
+        assert_eq!(steps_for(Path::new("/src/App.xcworkspace/contents.xcworkspacedata"), workspace_file), [Step::RebuildBuildServer]);
+        assert!(steps_for(Path::new("/src/Other.xcworkspace/contents.xcworkspacedata"), workspace_file).is_empty());
+        assert!(steps_for(Path::new("/src/README.md"), workspace_file).is_empty());
+    }
+
+    #[test]
+    fn lists_added_changed_and_removed_paths() {
+        let earlier = SystemTime::UNIX_EPOCH;
+        let later = earlier + Duration::from_secs(1);
+        let before = HashMap::from([
+            (PathBuf::from("Package.swift"), earlier),
+            (PathBuf::from("Podfile"), earlier),
+            (PathBuf::from("Podfile.lock"), earlier),
+        ]);
+        let after = HashMap::from([
+            (PathBuf::from("Package.swift"), earlier),
+            (PathBuf::from("Podfile"), later),
+            (PathBuf::from("Package.resolved"), later),
+        ]);
+        let mut changed = changed_paths(&before, &after);
+        changed.sort();
+        assert_eq!(changed, [PathBuf::from("Package.resolved"), PathBuf::from("Podfile"), PathBuf::from("Podfile.lock")]);
+        assert!(changed_paths(&after, &after).is_empty());
+    }
+}
@@ -0,0 +1,545 @@
+use std::fmt;
+use std::fs;
+use std::path::Path;
+use std::process::Command;
+
+use serde_json::Value;
+
+pub const DEFAULT_ISSUE_LIMIT: usize = 10;
+
+#[derive(Clone, Copy, PartialEq)]
+pub enum Severity {
+    Error,
+    Warning,
+}
+
+impl fmt::Display for Severity {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let name = match self {
+            Severity::Error => "error",
+            Severity::Warning => "warning",
+        };
+        write!(f, "{}", name)
+    }
+}
+
+pub struct Issue {
+    pub severity: Severity,
+    pub message: String,
+    // file:line, if the issue points at a source location
+    pub location: Option<String>,
+}
+
+// What we report about a build from its result bundle
+pub struct Digest {
+    pub error_count: usize,
+    pub warning_count: usize,
+    pub issues: Vec<Issue>,
+    pub duration: Option<f64>,
+    pub destination: Option<String>,
+}
+
+// Loads a digest from an .xcresult bundle, or from a JSON file holding xcresulttool output
+pub fn load(path: &Path) -> Result<Digest, String> {
+    if path.extension().is_some_and(|extension| extension == "json") {
+        let contents = fs::read_to_string(path).map_err(|error| format!("Could not read {}: {}", path.display(), error))?;
+        let json: Value = serde_json::from_str(&contents).map_err(|error| format!("{} is not valid JSON: {}", path.display(), error))?;
+        return Ok(parse(&json));
+    }
+    if !path.exists() {
+        return Err(format!("No result bundle at {}", path.display()));
+    }
+    Ok(parse(&xcresulttool_json(path)?))
+}
+
+// Xcode 16 has a build-results summary, older versions only have the raw (now "legacy") object graph
+fn xcresulttool_json(path: &Path) -> Result<Value, String> {
+    let path_str = path.to_string_lossy();
+    let invocations: [&[&str]; 3] = [
+        &["xcresulttool", "get", "build-results", "--path", &path_str],
+        &["xcresulttool", "get", "--legacy", "--format", "json", "--path", &path_str],
+        &["xcresulttool", "get", "--format", "json", "--path", &path_str],
+    ];
+    for args in invocations {
+        let output = match Command::new("xcrun").args(args).output() {
+            Ok(output) => output,
+            Err(error) => return Err(format!("failed to execute xcresulttool: {}", error)),
+        };
+        if !output.status.success() {
+            continue;
+        }
+        if let Ok(json) = serde_json::from_slice(&output.stdout) {
+            return Ok(json);
+        }
+    }
+    Err(format!("xcresulttool could not read {}", path.display()))
+}
+
+pub fn parse(json: &Value) -> Digest {
+    if json.get("_type").is_some() {
+        parse_legacy(json)
+    } else {
+        parse_build_results(json)
+    }
+}
+
+// `xcresulttool get build-results` output
+fn parse_build_results(json: &Value) -> Digest {
+    let mut issues = Vec::new();
+    for (key, severity) in [("errors", Severity::Error), ("warnings", Severity::Warning)] {
+        for issue in json.get(key).and_then(Value::as_array).into_iter().flatten() {
+            issues.push(Issue {
+                severity,
+                message: issue.get("message").and_then(Value::as_str).unwrap_or_default().to_string(),
+                location: issue.get("sourceURL").and_then(Value::as_str).and_then(source_location),
+            });
+        }
+    }
+    let duration = match (json.get("startTime").and_then(Value::as_f64), json.get("endTime").and_then(Value::as_f64)) {
+        (Some(start), Some(end)) => Some(end - start),
+        _ => None,
+    };
+    let destination = json.get("destination").map(|destination| {
+        let name = destination.get("deviceName").and_then(Value::as_str).unwrap_or("unknown device");
+        match destination.get("osVersion").and_then(Value::as_str) {
+            Some(os_version) => format!("{} ({})", name, os_version),
+            None => name.to_string(),
+        }
+    });
+    Digest {
+        error_count: count(json.get("errorCount"), &issues, Severity::Error),
+        warning_count: count(json.get("warningCount"), &issues, Severity::Warning),
+        issues,
+        duration,
+        destination,
+    }
+}
+
+// `xcresulttool get --format json` output, where every value is wrapped as {"_type": ..., "_value": ...}
+fn parse_legacy(json: &Value) -> Digest {
+    let mut issues = Vec::new();
+    let summaries = json.get("issues");
+    for (key, severity) in [("errorSummaries", Severity::Error), ("warningSummaries", Severity::Warning)] {
+        for issue in legacy_values(summaries.and_then(|summaries| summaries.get(key))) {
+            issues.push(Issue {
+                severity,
+                message: legacy_str(issue.get("message")).unwrap_or_default().to_string(),
+                location: legacy_str(issue.get("documentLocationInArchive").and_then(|location| location.get("url"))).and_then(source_location),
+            });
+        }
+    }
+    let metrics = json.get("metrics");
+    let action = legacy_values(json.get("actions")).first().copied();
+    let duration = action.and_then(|action| {
+        let started = legacy_str(action.get("startedTime")).and_then(parse_timestamp)?;
+        let ended = legacy_str(action.get("endedTime")).and_then(parse_timestamp)?;
+        Some(ended - started)
+    });
+    let destination = action
+        .and_then(|action| legacy_str(action.get("runDestination").and_then(|destination| destination.get("displayName"))))
+        .map(|name| name.to_string());
+    Digest {
+        error_count: count(metrics.and_then(|metrics| metrics.get("errorCount")).and_then(|count| count.get("_value")), &issues, Severity::Error),
+        warning_count: count(metrics.and_then(|metrics| metrics.get("warningCount")).and_then(|count| count.get("_value")), &issues, Severity::Warning),
+        issues,
+        duration,
+        destination,
+    }
+}
+
+fn legacy_values(value: Option<&Value>) -> Vec<&Value> {
+    value.and_then(|value| value.get("_values")).and_then(Value::as_array).map(|values| values.iter().collect()).unwrap_or_default()
+}
+
+fn legacy_str(value: Option<&Value>) -> Option<&str> {
+    value?.get("_value")?.as_str()
+}
+
+// Counts are numbers in build-results and strings in the legacy format
+fn count(value: Option<&Value>, issues: &[Issue], severity: Severity) -> usize {
+    value
+        .and_then(|value| value.as_u64().or_else(|| value.as_str().and_then(|count| count.parse().ok())))
+        .map(|count| count as usize)
+        .unwrap_or_else(|| issues.iter().filter(|issue| issue.severity == severity).count())
+}
+
+// Turns file:///path/File.swift#...&StartingLineNumber=41&... into /path/File.swift:42
+fn source_location(url: &str) -> Option<String> {
+    let (file, fragment) = url.split_once('#').unwrap_or((url, ""));
+    let file = file.strip_prefix("file://").unwrap_or(file);
+    if file.is_empty() {
+        return None;
+    }
+    let line = fragment
+        .split('&')
+        .find_map(|pair| pair.strip_prefix("StartingLineNumber="))
+        .and_then(|line| line.parse::<u64>().ok());
+    // Line numbers in result bundles are zero based
+    Some(match line {
+        Some(line) => format!("{}:{}", file, line + 1),
+        None => file.to_string(),
+    })
+}
+
+// Parses timestamps like 2024-03-05T10:11:12.345+0100 into seconds since the unix epoch
+fn parse_timestamp(timestamp: &str) -> Option<f64> {
+    let (date, time) = timestamp.split_once('T')?;
+    let mut date_parts = date.split('-').map(|part| part.parse::<i64>());
+    let (year, month, day) = (date_parts.next()?.ok()?, date_parts.next()?.ok()?, date_parts.next()?.ok()?);
+    let offset_start = time.find(['+', '-', 'Z']).unwrap_or(time.len());
+    let (clock, offset) = time.split_at(offset_start);
+    let mut clock_parts = clock.split(':').map(|part| part.parse::<f64>());
+    let (hours, minutes, seconds) = (clock_parts.next()?.ok()?, clock_parts.next()?.ok()?, clock_parts.next()?.ok()?);
+    let offset_seconds = match offset.chars().next() {
+        Some(sign @ ('+' | '-')) => {
+            let digits: String = offset[1..].chars().filter(|c| c.is_ascii_digit()).collect();
+            let hours = digits.get(0..2)?.parse::<f64>().ok()?;
+            let minutes = digits.get(2..4).and_then(|minutes| minutes.parse::<f64>().ok()).unwrap_or(0.0);
+            let seconds = hours * 3600.0 + minutes * 60.0;
+            if sign == '-' { -seconds } else { seconds }
+        }
+        _ => 0.0,
+    };
+    // Days since the epoch for the proleptic Gregorian calendar
+    let shifted_year = if month <= 2 { year - 1 } else { year };
+    let era = shifted_year.div_euclid(400);
+    let year_of_era = shifted_year - era * 400;
+    let day_of_year = (153 * ((month + 9) % 12) + 2) / 5 + day - 1;
+    let day_of_era = year_of_era * 365 + year_of_era / 4 - year_of_era / 100 + day_of_year;
+    let days = era * 146097 + day_of_era - 719468;
+    Some(days as f64 * 86400.0 + hours * 3600.0 + minutes * 60.0 + seconds - offset_seconds)
+}
+
+impl Digest {
+    pub fn print(&self, issue_limit: usize) {
+        say!("Result: {} errors, {} warnings", self.error_count, self.warning_count);
+        if let Some(duration) = self.duration {
+            say!("Duration: {:.1}s", duration);
+        }
+        if let Some(destination) = &self.destination {
+            say!("Destination: {}", destination);
+        }
+        // Errors first, they are what failed the build
+        let mut issues: Vec<&Issue> = self.issues.iter().filter(|issue| issue.severity == Severity::Error).collect();
+        issues.extend(self.issues.iter().filter(|issue| issue.severity == Severity::Warning));
+        for issue in issues.iter().take(issue_limit) {
+            match &issue.location {
+                Some(location) => say!("  {}: {}: {}", location, issue.severity, issue.message),
+                None => say!("  {}: {}", issue.severity, issue.message),
+            }
+        }
+        if issues.len() > issue_limit {
+            say!("  ... and {} more", issues.len() - issue_limit);
+        }
+    }
+}
+
+#[derive(Clone, Copy, PartialEq)]
+pub enum TestOutcome {
+    Passed,
+    Failed,
+    Skipped,
+}
+
+pub struct TestCase {
+    pub suite: String,
+    pub name: String,
+    pub outcome: TestOutcome,
+    pub duration: Option<f64>,
+    pub messages: Vec<String>,
+}
+
+pub struct TestSummary {
+    pub passed: usize,
+    pub failed: usize,
+    pub skipped: usize,
+    pub cases: Vec<TestCase>,
+}
+
+// Loads test results from an .xcresult bundle, or from a JSON file holding xcresulttool output
+pub fn load_tests(path: &Path) -> Result<TestSummary, String> {
+    if path.extension().is_some_and(|extension| extension == "json") {
+        let contents = fs::read_to_string(path).map_err(|error| format!("Could not read {}: {}", path.display(), error))?;
+        let json: Value = serde_json::from_str(&contents).map_err(|error| format!("{} is not valid JSON: {}", path.display(), error))?;
+        return Ok(parse_tests(&json));
+    }
+    if !path.exists() {
+        return Err(format!("No result bundle at {}", path.display()));
+    }
+    let path_str = path.to_string_lossy();
+    let invocations: [&[&str]; 2] = [
+        &["xcresulttool", "get", "test-results", "tests", "--path", &path_str],
+        &["xcresulttool", "get", "--legacy", "--format", "json", "--path", &path_str],
+    ];
+    for args in invocations {
+        let Some(json) = xcresulttool_output(args)? else {
+            continue;
+        };
+        if json.get("_type").is_none() {
+            return Ok(parse_tests(&json));
+        }
+        // Every test, not just the failures, is in the object the invocation record's testsRef points at
+        let tests = match legacy_tests_ref(&json) {
+            Some(id) => xcresulttool_output(&["xcresulttool", "get", "--legacy", "--format", "json", "--path", &path_str, "--id", id])?,
+            None => None,
+        };
+        return Ok(parse_legacy_tests(&json, tests.as_ref()));
+    }
+    Err(format!("xcresulttool could not read test results from {}", path.display()))
+}
+
+fn xcresulttool_output(args: &[&str]) -> Result<Option<Value>, String> {
+    let output = Command::new("xcrun").args(args).output().map_err(|error| format!("failed to execute xcresulttool: {}", error))?;
+    if !output.status.success() {
+        return Ok(None);
+    }
+    Ok(serde_json::from_slice(&output.stdout).ok())
+}
+
+fn legacy_tests_ref(json: &Value) -> Option<&str> {
+    legacy_values(json.get("actions"))
+        .into_iter()
+        .find_map(|action| legacy_str(action.get("actionResult")?.get("testsRef")?.get("id")))
+}
+
+pub fn parse_tests(json: &Value) -> TestSummary {
+    if json.get("_type").is_some() {
+        return parse_legacy_tests(json, None);
+    }
+    let mut cases = Vec::new();
+    for node in json.get("testNodes").and_then(Value::as_array).into_iter().flatten() {
+        collect_test_cases(node, "", &mut cases);
+    }
+    TestSummary {
+        passed: cases.iter().filter(|case| case.outcome == TestOutcome::Passed).count(),
+        failed: cases.iter().filter(|case| case.outcome == TestOutcome::Failed).count(),
+        skipped: cases.iter().filter(|case| case.outcome == TestOutcome::Skipped).count(),
+        cases,
+    }
+}
+
+// Walks the test plan > bundle > suite > case tree of `xcresulttool get test-results tests`
+fn collect_test_cases(node: &Value, suite: &str, cases: &mut Vec<TestCase>) {
+    let name = node.get("name").and_then(Value::as_str).unwrap_or_default();
+    let node_type = node.get("nodeType").and_then(Value::as_str).unwrap_or_default();
+    let children = node.get("children").and_then(Value::as_array);
+    if node_type == "Test Case" {
+        let outcome = match node.get("result").and_then(Value::as_str).unwrap_or_default() {
+            "Failed" => TestOutcome::Failed,
+            "Skipped" => TestOutcome::Skipped,
+            _ => TestOutcome::Passed,
+        };
+        let messages = children.into_iter().flatten()
+            .filter(|child| child.get("nodeType").and_then(Value::as_str) == Some("Failure Message"))
+            .filter_map(|child| child.get("name").and_then(Value::as_str).map(|message| message.to_string()))
+            .collect();
+        cases.push(TestCase {
+            suite: suite.to_string(),
+            name: name.to_string(),
+            outcome,
+            duration: node.get("duration").and_then(Value::as_str).and_then(|duration| duration.trim_end_matches('s').parse().ok()),
+            messages,
+        });
+        return;
+    }
+    let suite = if node_type == "Test Suite" { name } else { suite };
+    for child in children.into_iter().flatten() {
+        collect_test_cases(child, suite, cases);
+    }
+}
+
+// The legacy invocation record only carries counts and the failures. Passing tests are in the separate
+// ActionTestPlanRunSummaries object, without it the summary only lists the failed tests.
+fn parse_legacy_tests(json: &Value, tests: Option<&Value>) -> TestSummary {
+    let metrics = json.get("metrics");
+    let metric = |key: &str| {
+        legacy_str(metrics.and_then(|metrics| metrics.get(key)))
+            .and_then(|count| count.parse::<usize>().ok())
+            .unwrap_or(0)
+    };
+    let mut cases: Vec<TestCase> = Vec::new();
+    if let Some(tests) = tests {
+        collect_legacy_test_cases(tests, &mut cases);
+    }
+    for failure in legacy_values(json.get("issues").and_then(|issues| issues.get("testFailureSummaries"))) {
+        let identifier = legacy_str(failure.get("testCaseName")).unwrap_or_default();
+        let (suite, name) = identifier.rsplit_once('.').unwrap_or(("", identifier));
+        let mut message = legacy_str(failure.get("message")).unwrap_or_default().to_string();
+        if let Some(location) = legacy_str(failure.get("documentLocationInArchive").and_then(|location| location.get("url"))).and_then(source_location) {
+            message = format!("{}: {}", location, message);
+        }
+        match cases.iter_mut().find(|case| case.suite == suite && case.name == name) {
+            Some(case) => {
+                case.outcome = TestOutcome::Failed;
+                case.messages.push(message);
+            }
+            None => cases.push(TestCase {
+                suite: suite.to_string(),
+                name: name.to_string(),
+                outcome: TestOutcome::Failed,
+                duration: None,
+                messages: vec![message],
+            }),
+        }
+    }
+    if tests.is_some() {
+        return TestSummary {
+            passed: cases.iter().filter(|case| case.outcome == TestOutcome::Passed).count(),
+            failed: cases.iter().filter(|case| case.outcome == TestOutcome::Failed).count(),
+            skipped: cases.iter().filter(|case| case.outcome == TestOutcome::Skipped).count(),
+            cases,
+        };
+    }
+    let total = metric("testsCount");
+    let failed = metric("testsFailedCount").max(cases.len());
+    let skipped = metric("testsSkippedCount");
+    TestSummary {
+        passed: total.saturating_sub(failed + skipped),
+        failed,
+        skipped,
+        cases,
+    }
+}
+
+// The ActionTestMetadata leaves of a legacy ActionTestPlanRunSummaries object, whatever groups they are nested in
+fn collect_legacy_test_cases(node: &Value, cases: &mut Vec<TestCase>) {
+    if node.get("_type").and_then(|node_type| node_type.get("_name")).and_then(Value::as_str) == Some("ActionTestMetadata") {
+        // Identifiers look like AppTests/LoginTests/testLogin()
+        let identifier = legacy_str(node.get("identifier")).unwrap_or_default();
+        let name = legacy_str(node.get("name")).unwrap_or_default();
+        let suite = identifier.rsplit('/').nth(1).unwrap_or_default();
+        let outcome = match legacy_str(node.get("testStatus")) {
+            Some("Failure") => TestOutcome::Failed,
+            Some("Skipped") => TestOutcome::Skipped,
+            _ => TestOutcome::Passed,
+        };
+        cases.push(TestCase {
+            suite: suite.to_string(),
+            name: name.to_string(),
+            outcome,
+            duration: legacy_str(node.get("duration")).and_then(|duration| duration.parse().ok()),
+            messages: Vec::new(),
+        });
+        return;
+    }
+    match node {
+        Value::Object(fields) => fields.values().for_each(|value| collect_legacy_test_cases(value, cases)),
+        Value::Array(values) => values.iter().for_each(|value| collect_legacy_test_cases(value, cases)),
+        _ => (),
+    }
+}
+
+impl TestSummary {
+    pub fn print(&self) {
+        say!("Tests: {} passed, {} failed, {} skipped", self.passed, self.failed, self.skipped);
+        for case in self.cases.iter().filter(|case| case.outcome == TestOutcome::Failed) {
+            say!("  FAILED {}.{}", case.suite, case.name);
+            for message in &case.messages {
+                say!("    {}", message);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::json_fixture;
+
+    fn locations(digest: &Digest, severity: Severity) -> Vec<Option<&str>> {
+        digest.issues.iter().filter(|issue| issue.severity == severity).map(|issue| issue.location.as_deref()).collect()
+    }
+
+    #[test]
+    fn parses_build_results() {
+        let digest = parse(&json_fixture("xcresult/build-results.json"));
+        assert_eq!((digest.error_count, digest.warning_count), (1, 2));
+        assert_eq!(locations(&digest, Severity::Error), [Some("/Users/dev/App/Sources/ContentView.swift:42")]);
+        assert_eq!(locations(&digest, Severity::Warning), [Some("/Users/dev/App/Sources/Counter.swift:8"), None]);
+        assert_eq!(digest.issues[0].message, "Cannot find 'viewModel' in scope");
+        assert_eq!(digest.duration, Some(23.25));
+        assert_eq!(digest.destination.as_deref(), Some("iPhone 15 (17.5)"));
+    }
+
+    #[test]
+    fn parses_legacy_results() {
+        let digest = parse(&json_fixture("xcresult/legacy.json"));
+        assert_eq!((digest.error_count, digest.warning_count), (2, 1));
+        assert_eq!(locations(&digest, Severity::Error), [Some("/Users/dev/App/Sources/Model.swift:10"), None]);
+        assert_eq!(locations(&digest, Severity::Warning), [Some("/Users/dev/App/Sources/Legacy.m:21")]);
+        assert_eq!(digest.issues[1].message, "Command SwiftCompile failed with a nonzero exit code");
+        assert!(digest.duration.is_some_and(|duration| (duration - 90.5).abs() < 1e-6));
+        assert_eq!(digest.destination.as_deref(), Some("Any iOS Simulator Device"));
+    }
+
+    #[test]
+    fn counts_issues_when_counts_are_missing() {
+        let mut json = json_fixture("xcresult/build-results.json");
+        let object = json.as_object_mut().expect("Fixture is an object");
+        object.remove("errorCount");
+        object.remove("warningCount");
+        let digest = parse(&json);
+        assert_eq!((digest.error_count, digest.warning_count), (1, 2));
+    }
+
+    fn outcomes(summary: &TestSummary) -> Vec<(&str, &str, bool)> {
+        summary.cases.iter().map(|case| (case.suite.as_str(), case.name.as_str(), case.outcome == TestOutcome::Failed)).collect()
+    }
+
+    #[test]
+    fn parses_test_results() {
+        let summary = parse_tests(&json_fixture("xcresult/test-results.json"));
+        assert_eq!((summary.passed, summary.failed, summary.skipped), (2, 1, 1));
+        assert_eq!(outcomes(&summary), [
+            ("LoginTests", "testLogin()", false),
+            ("LoginTests", "testLogout()", true),
+            ("LoginTests", "testBiometrics()", false),
+            ("PriceTests", "testFormat()", false),
+        ]);
+        let failed = &summary.cases[1];
+        assert_eq!(failed.duration, Some(0.3));
+        assert_eq!(failed.messages, ["LoginTests.swift:27: XCTAssertEqual failed: (\"guest\") is not equal to (\"admin\")"]);
+        assert!(summary.cases[2].outcome == TestOutcome::Skipped);
+    }
+
+    #[test]
+    fn parses_legacy_test_failures_without_the_tests_object() {
+        let summary = parse_tests(&json_fixture("xcresult/legacy-tests.json"));
+        assert_eq!((summary.passed, summary.failed, summary.skipped), (2, 1, 1));
+        assert_eq!(outcomes(&summary), [("LoginTests", "testLogout()", true)]);
+        assert_eq!(summary.cases[0].messages, ["/Users/dev/App/AppTests/LoginTests.swift:27: XCTAssertEqual failed: (\"guest\") is not equal to (\"admin\") & <retry>"]);
+    }
+
+    #[test]
+    fn parses_every_legacy_test_from_the_tests_object() {
+        let record = json_fixture("xcresult/legacy-tests.json");
+        assert_eq!(legacy_tests_ref(&record), Some("0~tY3yX8pL2qRm"));
+        let summary = parse_legacy_tests(&record, Some(&json_fixture("xcresult/legacy-test-summaries.json")));
+        assert_eq!((summary.passed, summary.failed, summary.skipped), (2, 1, 1));
+        assert_eq!(outcomes(&summary), [
+            ("LoginTests", "testBiometrics()", false),
+            ("LoginTests", "testLogin()", false),
+            ("LoginTests", "testLogout()", true),
+            ("PriceTests", "testFormat()", false),
+        ]);
+        assert_eq!(summary.cases[2].duration, Some(0.3));
+        assert_eq!(summary.cases[2].messages.len(), 1);
+    }
+
+    #[test]
+    fn turns_source_urls_into_one_based_locations() {
+        assert_eq!(source_location("file:///a/B.swift#EndingLineNumber=3&StartingLineNumber=2").as_deref(), Some("/a/B.swift:3"));
+        assert_eq!(source_location("file:///a/B.swift").as_deref(), Some("/a/B.swift"));
+        assert_eq!(source_location("/a/B.swift#CharacterRangeLen=0").as_deref(), Some("/a/B.swift"));
+        assert_eq!(source_location("file://"), None);
+    }
+
+    #[test]
+    fn parses_timestamps_with_offsets() {
+        assert_eq!(parse_timestamp("1970-01-01T00:00:00Z"), Some(0.0));
+        assert!(parse_timestamp("2024-03-05T10:11:12.345+0100").is_some_and(|seconds| (seconds - 1709629872.345).abs() < 1e-3));
+        assert_eq!(parse_timestamp("2000-01-01T00:00:00+01:00"), Some(946681200.0));
+        assert_eq!(parse_timestamp("1999-12-31T18:00:00-0500"), Some(946681200.0));
+        assert_eq!(parse_timestamp("yesterday"), None);
+    }
+}
@@ -0,0 +1,844 @@
+use std::fs;
+use std::os::unix::fs::PermissionsExt;
+use std::path::{Path, PathBuf};
+use std::process::{Command, Output};
+
+#[allow(dead_code)]
+#[path = "../src/test_support.rs"]
+mod test_support;
+
+use test_support::fixture;
+
+// A scratch directory per test, used as both HOME (so sass writes its config there) and working directory
+fn scratch_dir(name: &str) -> PathBuf {
+    let dir = std::env::temp_dir().join(format!("sass-tests-{}-{}", std::process::id(), name));
+    let _ = fs::remove_dir_all(&dir);
+    fs::create_dir_all(dir.join(".config")).expect("Could not create scratch dir");
+    dir
+}
+
+// Tools installed with fake_tool come first on the PATH
+fn sass(dir: &Path, args: &[&str]) -> Output {
+    let path = format!("{}:{}", dir.join("bin").display(), std::env::var("PATH").unwrap_or_default());
+    Command::new(env!("CARGO_BIN_EXE_sass"))
+        .args(args)
+        .current_dir(dir)
+        .env("HOME", dir)
+        .env("PATH", path)
+        .env_remove("RUST_BACKTRACE")
+        .output()
+        .expect("Could not run sass")
+}
+
+// A shell script standing in for an external tool, which appends its arguments to <dir>/<name>.args
+fn fake_tool(dir: &Path, name: &str, script: &str) {
+    let bin = dir.join("bin");
+    fs::create_dir_all(&bin).expect("Could not create bin dir");
+    let path = bin.join(name);
+    fs::write(&path, format!("#!/bin/sh\necho \"$@\" >> \"{}/{}.args\"\n{}\n", dir.display(), name, script)).expect("Could not write fake tool");
+    fs::set_permissions(&path, fs::Permissions::from_mode(0o755)).expect("Could not make fake tool executable");
+}
+
+fn tool_calls(dir: &Path, name: &str) -> Vec<String> {
+    fs::read_to_string(dir.join(format!("{}.args", name))).unwrap_or_default().lines().map(str::to_string).collect()
+}
+
+fn write_config(dir: &Path, contents: &str) {
+    fs::create_dir_all(dir.join(".config/sass")).expect("Could not create config dir");
+    fs::write(dir.join(".config/sass/config.toml"), contents).expect("Could not write config");
+}
+
+fn git_init(dir: &Path) {
+    let status = Command::new("git").args(["init", "-q"]).current_dir(dir).status().expect("Could not run git");
+    assert!(status.success());
+}
+
+fn stdout(output: &Output) -> String {
+    String::from_utf8_lossy(&output.stdout).into_owned()
+}
+
+#[test]
+fn passes_arguments_after_the_command_flag() {
+    let dir = scratch_dir("arguments");
+    let output = sass(&dir, &["--derived-clean", "--parts", "bogus"]);
+    let stdout = stdout(&output);
+    assert!(!stdout.contains("Too many arguments"), "{}", stdout);
+    assert!(stdout.contains("Unknown derived data part 'bogus'"), "{}", stdout);
+}
+
+#[test]
+fn options_may_come_before_the_command_flag() {
+    let dir = scratch_dir("option-order");
+    let output = sass(&dir, &["--parts", "bogus", "-dc"]);
+    assert!(stdout(&output).contains("Unknown derived data part 'bogus'"), "{}", stdout(&output));
+}
+
+#[test]
+fn derived_clean_only_touches_the_configured_project() {
+    let dir = scratch_dir("derived-clean-project");
+    write_config(&dir, "workspace_name = \"App\"\n");
+    let derived_data = dir.join("Library/Developer/Xcode/DerivedData");
+    for folder in ["App-abc", "App-Widgets-def"] {
+        fs::create_dir_all(derived_data.join(folder).join("Index.noindex")).unwrap();
+        fs::write(derived_data.join(folder).join("info.plist"), "plist").unwrap();
+    }
+    let output = sass(&dir, &["--derived-clean", "--parts", "index"]);
+    assert!(output.status.success(), "{}", stderr(&output));
+    assert!(!derived_data.join("App-abc/Index.noindex").exists());
+    assert!(derived_data.join("App-Widgets-def/Index.noindex").exists());
+}
+
+fn derived_data_folder(root: &Path) -> PathBuf {
+    let folder = root.join("App-abc");
+    fs::create_dir_all(&folder).unwrap();
+    fs::write(folder.join("info.plist"), "plist").unwrap();
+    folder
+}
+
+#[test]
+fn derived_data_location_from_config_wins_and_is_relative_to_the_git_root() {
+    let dir = scratch_dir("derived-data-config");
+    git_init(&dir);
+    write_config(&dir, "derived_data_location = \"Build/DerivedData\"\n");
+    fake_tool(&dir, "defaults", &format!("echo '{}/xcode-dd'", dir.display()));
+    let configured = derived_data_folder(&dir.join("Build/DerivedData"));
+    let from_xcode = derived_data_folder(&dir.join("xcode-dd"));
+    let output = sass(&dir, &["-w"]);
+    assert!(output.status.success(), "{}", stderr(&output));
+    assert!(!configured.exists());
+    assert!(from_xcode.exists());
+}
+
+#[test]
+fn derived_data_location_falls_back_to_xcode_preferences() {
+    let dir = scratch_dir("derived-data-xcode");
+    git_init(&dir);
+    fake_tool(&dir, "defaults", "echo '~/xcode-dd'");
+    let from_xcode = derived_data_folder(&dir.join("xcode-dd"));
+    let default = derived_data_folder(&dir.join("Library/Developer/Xcode/DerivedData"));
+    let output = sass(&dir, &["-w"]);
+    assert!(output.status.success(), "{}", stderr(&output));
+    assert!(!from_xcode.exists());
+    assert!(default.exists());
+}
+
+#[test]
+fn picks_the_command_flag_among_other_arguments() {
+    let dir = scratch_dir("command-flag");
+    let output = sass(&dir, &["--verbose", "-w", "extra"]);
+    assert!(output.status.success());
+    assert!(!stdout(&output).contains("Too many arguments"), "{}", stdout(&output));
+    assert!(stdout(&output).contains("Cleaning DerivedData..."), "{}", stdout(&output));
+}
+
+#[test]
+fn unknown_commands_print_help_and_fail() {
+    let dir = scratch_dir("unknown");
+    let output = sass(&dir, &["--bogus"]);
+    assert!(!output.status.success());
+    assert!(stdout(&output).contains("Unknown command --bogus"), "{}", stdout(&output));
+    assert!(stdout(&output).contains("Help:"), "{}", stdout(&output));
+}
+
+fn stderr(output: &Output) -> String {
+    String::from_utf8_lossy(&output.stderr).into_owned()
+}
+
+fn json_events(output: &Output) -> Vec<serde_json::Value> {
+    stdout(output)
+        .lines()
+        .map(|line| serde_json::from_str(line).unwrap_or_else(|error| panic!("{} is not JSON: {}", line, error)))
+        .collect()
+}
+
+#[test]
+fn doctor_reports_failures_without_a_panic() {
+    let dir = scratch_dir("doctor");
+    let output = sass(&dir, &["--doctor"]);
+    // The scratch dir is no git repository, so at least that check fails
+    assert_eq!(output.status.code(), Some(1));
+    assert!(!stderr(&output).contains("panicked"), "{}", stderr(&output));
+    assert!(stderr(&output).contains("checks failed"), "{}", stderr(&output));
+}
+
+#[test]
+fn doctor_prints_json() {
+    let dir = scratch_dir("doctor-json");
+    let events = json_events(&sass(&dir, &["--doctor", "--json"]));
+    let result = events.iter().find(|event| event["event"] == "result" && event["kind"] == "doctor").expect("No doctor result");
+    assert!(result["data"]["checks"].as_array().is_some_and(|checks| checks.iter().any(|check| check["name"] == "git root")));
+    assert_eq!(events.last().map(|event| event["status"].clone()), Some("failure".into()));
+}
+
+#[test]
+fn forwards_arguments_after_double_dash_to_xcodebuild() {
+    let dir = scratch_dir("passthrough");
+    git_init(&dir);
+    fake_tool(&dir, "xcodebuild", "exit 0");
+    sass(&dir, &["-r", "--", "-quiet", "-r"]);
+    let calls = tool_calls(&dir, "xcodebuild");
+    let build = calls.iter().find(|call| call.contains("-scheme")).expect("xcodebuild was not run");
+    assert!(build.contains("-quiet -r"), "{}", build);
+}
+
+const PROFILES_CONFIG: &str = r#"
+scheme = "App"
+workspace_name = "App"
+
+[profiles.widget]
+scheme = "Widget"
+
+[profiles.app]
+scheme = "App"
+"#;
+
+#[test]
+fn completions_list_profiles() {
+    let dir = scratch_dir("completions");
+    write_config(&dir, PROFILES_CONFIG);
+    let completions = stdout(&sass(&dir, &["--completions"]));
+    assert!(completions.starts_with("#compdef sass"), "{}", completions);
+    assert!(completions.contains("--profile[build profile from the config]:profile:(app widget)"), "{}", completions);
+}
+
+#[test]
+fn profile_flag_selects_a_profile() {
+    let dir = scratch_dir("profile");
+    git_init(&dir);
+    write_config(&dir, PROFILES_CONFIG);
+    fake_tool(&dir, "xcodebuild", "exit 0");
+    sass(&dir, &["-r", "--profile", "widget"]);
+    let calls = tool_calls(&dir, "xcodebuild");
+    assert!(calls.iter().any(|call| call.contains("-scheme Widget")), "{:?}", calls);
+}
+
+#[test]
+fn unknown_profile_fails() {
+    let dir = scratch_dir("unknown-profile");
+    write_config(&dir, PROFILES_CONFIG);
+    let output = sass(&dir, &["--derived-clean", "--profile", "nope"]);
+    assert_eq!(output.status.code(), Some(1));
+    assert_eq!(stderr(&output).trim(), "Error: Profile nope not found in config");
+}
+
+#[test]
+fn builds_the_selected_schemes() {
+    let dir = scratch_dir("schemes");
+    git_init(&dir);
+    fake_tool(&dir, "xcodebuild", "exit 0");
+    sass(&dir, &["-r", "--schemes", "A,B", "--jobs", "2"]);
+    let calls = tool_calls(&dir, "xcodebuild");
+    for scheme in ["A", "B"] {
+        let call = calls.iter().find(|call| call.contains(&format!("-scheme {} ", scheme))).expect("Scheme was not built");
+        // Parallel builds get a DerivedData folder each
+        assert!(call.contains("-derivedDataPath"), "{}", call);
+    }
+}
+
+#[test]
+fn rejects_an_empty_scheme_list() {
+    let dir = scratch_dir("empty-schemes");
+    git_init(&dir);
+    fake_tool(&dir, "xcodebuild", "exit 0");
+    for schemes in [",", ""] {
+        let output = sass(&dir, &["-r", "--schemes", schemes]);
+        assert_eq!(output.status.code(), Some(1));
+        assert!(stderr(&output).contains("--schemes needs at least one scheme name"), "{}", stderr(&output));
+    }
+    assert!(tool_calls(&dir, "xcodebuild").is_empty());
+}
+
+fn launcher_settings(dir: &Path) -> Vec<String> {
+    let calls = tool_calls(dir, "xcodebuild");
+    calls[0].split(' ').filter(|arg| arg.starts_with("CC=") || arg.starts_with("CXX=")).map(str::to_string).collect()
+}
+
+#[test]
+fn rebuild_picks_sccache_before_ccache() {
+    let dir = scratch_dir("launcher-auto");
+    git_init(&dir);
+    for tool in ["xcodebuild", "sccache", "ccache"] {
+        fake_tool(&dir, tool, "exit 0");
+    }
+    let output = sass(&dir, &["-r"]);
+    assert!(stdout(&output).contains("Using sccache as compiler launcher."), "{}", stdout(&output));
+    let settings = launcher_settings(&dir);
+    assert_eq!(settings.len(), 2, "{:?}", settings);
+    assert!(settings[0].ends_with("sass-launcher/sccache/clang"), "{:?}", settings);
+    assert_eq!(tool_calls(&dir, "sccache"), ["--zero-stats", "--show-stats"]);
+}
+
+#[test]
+fn rebuild_uses_the_configured_launcher() {
+    let dir = scratch_dir("launcher-named");
+    git_init(&dir);
+    for tool in ["xcodebuild", "sccache", "ccache"] {
+        fake_tool(&dir, tool, "exit 0");
+    }
+    write_config(&dir, "compiler_launcher = \"ccache\"\n");
+    sass(&dir, &["-r"]);
+    assert!(launcher_settings(&dir)[0].ends_with("sass-launcher/ccache/clang"), "{:?}", launcher_settings(&dir));
+    assert!(tool_calls(&dir, "sccache").is_empty());
+}
+
+#[test]
+fn rebuild_without_a_launcher() {
+    let dir = scratch_dir("launcher-none");
+    git_init(&dir);
+    for tool in ["xcodebuild", "sccache"] {
+        fake_tool(&dir, tool, "exit 0");
+    }
+    write_config(&dir, "compiler_launcher = \"none\"\n");
+    sass(&dir, &["-r"]);
+    assert!(launcher_settings(&dir).is_empty());
+
+    write_config(&dir, "compiler_launcher = \"distcc\"\n");
+    let output = sass(&dir, &["-r"]);
+    assert!(stdout(&output).contains("compiler launcher distcc not found, building without it."), "{}", stdout(&output));
+}
+
+#[test]
+fn failed_build_fails_rebuild() {
+    let dir = scratch_dir("failed-build");
+    git_init(&dir);
+    fake_tool(&dir, "xcodebuild", r#"case "$*" in *"-scheme B "*) exit 65;; esac"#);
+    fake_tool(&dir, "xcode-build-server", "exit 0");
+    let output = sass(&dir, &["-r", "--schemes", "A,B"]);
+    assert_eq!(output.status.code(), Some(1));
+    assert!(stderr(&output).contains("Build failed for B"), "{}", stderr(&output));
+    assert!(tool_calls(&dir, "xcode-build-server").is_empty());
+}
+
+#[test]
+fn results_show_reads_a_result_file() {
+    let dir = scratch_dir("results-show");
+    let output = stdout(&sass(&dir, &["--results-show", &fixture("xcresult/build-results.json").display().to_string(), "--issues", "1"]));
+    assert!(output.contains("Result: 1 errors, 2 warnings"), "{}", output);
+    assert!(output.contains("/Users/dev/App/Sources/ContentView.swift:42: error: Cannot find 'viewModel' in scope"), "{}", output);
+    assert!(output.contains("... and 2 more"), "{}", output);
+}
+
+#[test]
+fn parse_log_groups_a_log_file() {
+    let dir = scratch_dir("parse-log");
+    let output = stdout(&sass(&dir, &["--parse-log", &fixture("logs/build.log").display().to_string()]));
+    assert!(output.contains("/Users/dev/App/Sources/Legacy.m\n  3:9 error [compiler]: 'Analytics/Analytics.h' file not found"), "{}", output);
+    assert!(output.contains("8 errors, 2 warnings"), "{}", output);
+}
+
+#[test]
+fn test_runs_a_test_plan_without_building_and_writes_junit() {
+    let dir = scratch_dir("test");
+    git_init(&dir);
+    fake_tool(&dir, "xcodebuild", "mkdir -p .bundle-tests");
+    fake_tool(&dir, "xcrun", &format!("cat '{}'", fixture("xcresult/test-results.json").display()));
+    let junit = dir.join("results.xml");
+    let output = sass(&dir, &["--test", "--without-building", "--test-plan", "Smoke", "--junit", &junit.display().to_string()]);
+    assert_eq!(output.status.code(), Some(1));
+    assert!(stderr(&output).contains("1 tests failed"), "{}", stderr(&output));
+    let calls = tool_calls(&dir, "xcodebuild");
+    assert!(calls[0].starts_with("test-without-building "), "{:?}", calls);
+    assert!(calls[0].contains("-testPlan Smoke"), "{:?}", calls);
+    let xml = fs::read_to_string(&junit).expect("JUnit results were not written");
+    assert!(xml.contains("<testsuites tests=\"4\" failures=\"1\" skipped=\"1\">"), "{}", xml);
+}
+
+#[test]
+fn json_combines_with_a_command() {
+    let dir = scratch_dir("json-wipe");
+    git_init(&dir);
+    let derived_data = dir.join("Library/Developer/Xcode/DerivedData/App-abc");
+    fs::create_dir_all(&derived_data).unwrap();
+    fs::write(derived_data.join("info.plist"), "plist").unwrap();
+    let output = sass(&dir, &["-w", "--json"]);
+    assert!(output.status.success(), "{}", stderr(&output));
+    let events = json_events(&output);
+    assert_eq!(events[0]["event"], "command_started");
+    assert_eq!(events[0]["command"], "generic_wipe_derived");
+    assert!(events.iter().any(|event| event["event"] == "deleted"), "{:?}", events);
+    let finished = events.last().unwrap();
+    assert_eq!((&finished["event"], &finished["status"]), (&"finished".into(), &"success".into()));
+}
+
+#[test]
+fn json_reports_failed_builds() {
+    let dir = scratch_dir("json-failed-build");
+    git_init(&dir);
+    fake_tool(&dir, "xcodebuild", "exit 65");
+    fake_tool(&dir, "xcode-build-server", "exit 0");
+    let output = sass(&dir, &["--json", "-r"]);
+    assert_eq!(output.status.code(), Some(1));
+    let events = json_events(&output);
+    let build = events.iter().find(|event| event["event"] == "result" && event["kind"] == "build").expect("No build result");
+    assert_eq!(build["data"]["success"], false);
+    let xcodebuild = events.iter().find(|event| event["event"] == "subprocess" && event["program"] == "xcodebuild").expect("No xcodebuild subprocess event");
+    assert_eq!(xcodebuild["exit_code"], 65);
+    let finished = events.last().unwrap();
+    assert_eq!(finished["event"], "finished");
+    assert_eq!(finished["status"], "failure");
+    assert!(finished["error"].as_str().unwrap().starts_with("Build failed"), "{}", finished);
+}
+
+#[test]
+fn build_server_configures_sourcekit_lsp_for_a_swift_package() {
+    let dir = scratch_dir("build-server-swiftpm");
+    git_init(&dir);
+    fs::write(dir.join("Package.swift"), "").unwrap();
+    fs::write(dir.join("buildServer.json"), "{}").unwrap();
+    fake_tool(&dir, "swift", "exit 0");
+    let output = sass(&dir, &["--build-server"]);
+    assert!(output.status.success(), "{}", stderr(&output));
+    assert!(!dir.join("buildServer.json").exists());
+    assert!(dir.join(".sourcekit-lsp/config.json").exists());
+    assert_eq!(tool_calls(&dir, "swift"), ["package resolve"]);
+}
+
+#[test]
+fn build_server_explains_a_missing_xcode_build_server() {
+    let dir = scratch_dir("build-server-missing");
+    git_init(&dir);
+    write_config(&dir, "workspace_name = \"App\"\nscheme = \"App\"\n");
+    fs::create_dir_all(dir.join("App.xcworkspace")).unwrap();
+    let output = sass(&dir, &["--build-server"]);
+    assert_eq!(output.status.code(), Some(1));
+    assert!(stderr(&output).contains("xcode-build-server not found on PATH"), "{}", stderr(&output));
+}
+
+#[test]
+fn install_packages_resolves_workspace_packages_from_the_lockfile() {
+    let dir = scratch_dir("workspace-packages");
+    git_init(&dir);
+    write_config(&dir, "workspace_name = \"App\"\n");
+    let swiftpm = dir.join("App.xcworkspace/xcshareddata/swiftpm");
+    fs::create_dir_all(&swiftpm).unwrap();
+    fs::copy(fixture("spm/Package.resolved"), swiftpm.join("Package.resolved")).unwrap();
+    fake_tool(&dir, "xcodebuild", "exit 0");
+    let output = sass(&dir, &["--install-packages"]);
+    assert!(output.status.success(), "{}", stderr(&output));
+    assert_eq!(tool_calls(&dir, "xcodebuild"), ["-resolvePackageDependencies -onlyUsePackageVersionsFromResolvedFile -workspace App.xcworkspace"]);
+
+    write_config(&dir, "workspace_name = \"App\"\nscheme = \"App\"\n");
+    sass(&dir, &["--install-packages"]);
+    assert!(tool_calls(&dir, "xcodebuild")[1].ends_with("-workspace App.xcworkspace -scheme App"), "{:?}", tool_calls(&dir, "xcodebuild"));
+}
+
+#[test]
+fn failing_deps_script_fails_the_command() {
+    let dir = scratch_dir("deps-script-failure");
+    git_init(&dir);
+    fs::write(dir.join("setup.sh"), "exit 3\n").unwrap();
+    write_config(&dir, "[[deps_scripts]]\npath = \"/setup.sh\"\n");
+    let output = sass(&dir, &["--deps-script"]);
+    assert_eq!(output.status.code(), Some(1));
+    assert!(stderr(&output).contains("setup.sh failed with exit status: 3"), "{}", stderr(&output));
+}
+
+#[test]
+fn deps_script_timeout_kills_the_script() {
+    let dir = scratch_dir("deps-script-timeout");
+    git_init(&dir);
+    fs::write(dir.join("slow.sh"), "sleep 30\n").unwrap();
+    write_config(&dir, "[[deps_scripts]]\npath = \"slow.sh\"\ntimeout_seconds = 1\n");
+    let started = std::time::Instant::now();
+    let output = sass(&dir, &["--deps-script"]);
+    assert!(started.elapsed() < std::time::Duration::from_secs(10), "{:?}", started.elapsed());
+    assert_eq!(output.status.code(), Some(1));
+    assert!(stderr(&output).contains("timed out after 1s"), "{}", stderr(&output));
+}
+
+// A DerivedData folder for -w to delete, so hooks run around a command with a visible effect
+fn hooked_project(name: &str, hooks: &str) -> (PathBuf, PathBuf) {
+    let dir = scratch_dir(name);
+    git_init(&dir);
+    write_config(&dir, &format!("scheme = \"App\"\n\n[hooks]\n{}\n", hooks));
+    let derived_data = dir.join("Library/Developer/Xcode/DerivedData/App-abc");
+    fs::create_dir_all(&derived_data).unwrap();
+    fs::write(derived_data.join("info.plist"), "plist").unwrap();
+    (dir, derived_data)
+}
+
+#[test]
+fn failing_pre_hook_stops_the_command() {
+    let (dir, derived_data) = hooked_project("pre-hook-failure", "pre_wipe_derived = [\"exit 2\"]");
+    let output = sass(&dir, &["-w"]);
+    assert_eq!(output.status.code(), Some(1));
+    assert!(stderr(&output).contains("pre_wipe_derived hook `exit 2` failed"), "{}", stderr(&output));
+    assert!(derived_data.exists());
+}
+
+#[test]
+fn failing_post_hook_fails_the_command() {
+    let (dir, derived_data) = hooked_project("post-hook-failure", "post_wipe_derived = [\"exit 4\"]");
+    let output = sass(&dir, &["-w"]);
+    assert_eq!(output.status.code(), Some(1));
+    assert!(stderr(&output).contains("post_wipe_derived hook `exit 4` failed"), "{}", stderr(&output));
+    assert!(!derived_data.exists());
+}
+
+#[test]
+fn hooks_see_the_command_and_its_outcome() {
+    let (dir, derived_data) = hooked_project("hook-env", "post_wipe_derived = [\"env | grep ^SASS > hook-env.txt\"]");
+    let output = sass(&dir, &["-w"]);
+    assert!(output.status.success(), "{}", stderr(&output));
+    let env = fs::read_to_string(dir.join("hook-env.txt")).expect("Hook did not run in the git root");
+    for expected in [
+        "SASS_HOOK=post_wipe_derived".to_string(),
+        "SASS_COMMAND=generic_wipe_derived".to_string(),
+        format!("SASS={}", env!("CARGO_BIN_EXE_sass")),
+        "SASS_SCHEME=App".to_string(),
+        "SASS_STATUS=success".to_string(),
+        format!("SASS_DELETED_PATHS={}", derived_data.display()),
+    ] {
+        assert!(env.lines().any(|line| line == expected), "{} not in {}", expected, env);
+    }
+    assert!(env.lines().any(|line| line.starts_with("SASS_PROJECT_ROOT=") && line.ends_with(dir.file_name().unwrap().to_str().unwrap())), "{}", env);
+}
+
+#[test]
+fn commands_run_from_hooks_skip_their_own_hooks() {
+    let (dir, _) = hooked_project("nested-hooks", "pre_wipe_derived = [\"echo pre >> hook-runs.txt\", \"\\\"$SASS\\\" -w\"]");
+    let output = sass(&dir, &["-w"]);
+    assert!(output.status.success(), "{}", stderr(&output));
+    assert_eq!(fs::read_to_string(dir.join("hook-runs.txt")).unwrap(), "pre\n");
+}
+
+#[test]
+fn offline_clean_keeps_caches_and_skips_package_updates() {
+    let dir = scratch_dir("offline-clean");
+    git_init(&dir);
+    fs::write(dir.join("Package.swift"), "").unwrap();
+    for tool in ["swift", "xcodebuild", "xcode-build-server"] {
+        fake_tool(&dir, tool, "exit 0");
+    }
+    let output = sass(&dir, &["-c", "--offline"]);
+    assert!(output.status.success(), "{}", stderr(&output));
+    assert!(stdout(&output).contains("Offline: keeping the SwiftPM cache"), "{}", stdout(&output));
+    let swift = tool_calls(&dir, "swift");
+    assert!(!swift.iter().any(|call| call.contains("purge-cache")), "{:?}", swift);
+    assert!(swift.iter().filter(|call| call.starts_with("package resolve")).all(|call| call.contains("--skip-update")), "{:?}", swift);
+    let xcodebuild = tool_calls(&dir, "xcodebuild");
+    assert!(xcodebuild[0].starts_with("-skipPackageUpdates -disableAutomaticPackageResolution "), "{:?}", xcodebuild);
+}
+
+#[test]
+fn offline_skips_package_updates() {
+    let dir = scratch_dir("offline-update");
+    git_init(&dir);
+    fs::write(dir.join("Package.swift"), "").unwrap();
+    fake_tool(&dir, "swift", "exit 0");
+    let output = sass(&dir, &["--packages-update", "--offline"]);
+    assert!(output.status.success(), "{}", stderr(&output));
+    assert!(stdout(&output).contains("Offline: skipping swift package update."), "{}", stdout(&output));
+    assert!(tool_calls(&dir, "swift").is_empty());
+}
+
+#[test]
+fn updates_named_packages_only_where_they_are_pinned() {
+    let dir = scratch_dir("packages-update");
+    git_init(&dir);
+    for (package, lockfile) in [("Core", "Package.resolved"), ("Tools", "Package.resolved.v1"), ("Empty", "missing")] {
+        fs::create_dir_all(dir.join(package)).unwrap();
+        fs::write(dir.join(package).join("Package.swift"), "").unwrap();
+        let _ = fs::copy(fixture(&format!("spm/{}", lockfile)), dir.join(package).join("Package.resolved"));
+    }
+    fake_tool(&dir, "swift", &format!("echo \"$(basename \"$PWD\") $*\" >> '{}/swift-cwd.args'", dir.display()));
+    let output = sass(&dir, &["--packages-update", "swift-argument-parser", "Alamofire", "nope"]);
+    assert!(output.status.success(), "{}", stderr(&output));
+    let mut calls = tool_calls(&dir, "swift-cwd");
+    calls.sort();
+    assert_eq!(calls, ["Core package update swift-argument-parser", "Tools package update Alamofire"]);
+    assert!(stdout(&output).contains("No Package.resolved pins nope"), "{}", stdout(&output));
+}
+
+const SWIFT_SYNTAX_ARTIFACT: &str = "https___github_com_apple_swift_syntax_releases_download_600_0_0_SwiftSyntax_xcframework_zip";
+
+// A project pinning swift-argument-parser and kingfisher, and a SwiftPM cache with those and one artifact
+fn spm_cache_dir(name: &str) -> PathBuf {
+    let dir = scratch_dir(name);
+    git_init(&dir);
+    fs::create_dir_all(dir.join("Core")).unwrap();
+    fs::copy(fixture("spm/Package.resolved"), dir.join("Core/Package.resolved")).unwrap();
+    let cache = dir.join(".cache/org.swift.swiftpm");
+    for entry in ["repositories/swift-argument-parser-1a2b3c4d5e", "repositories/Kingfisher-9f8e7d6c5b", &format!("artifacts/{}", SWIFT_SYNTAX_ARTIFACT)] {
+        fs::create_dir_all(cache.join(entry)).unwrap();
+        fs::write(cache.join(entry).join("data"), "data").unwrap();
+    }
+    dir
+}
+
+fn cached(dir: &Path, entry: &str) -> bool {
+    dir.join(".cache/org.swift.swiftpm").join(entry).exists()
+}
+
+#[test]
+fn spm_cache_lists_entries() {
+    let dir = spm_cache_dir("spm-cache-list");
+    let output = stdout(&sass(&dir, &["--spm-cache", "list"]));
+    assert!(output.lines().any(|line| line.starts_with("repository  swift-argument-parser") && line.contains(" used ")), "{}", output);
+    assert!(output.lines().any(|line| line.starts_with("artifact") && line.contains(" unused ")), "{}", output);
+    assert!(output.contains("3 entries, 12 B total"), "{}", output);
+}
+
+#[test]
+fn spm_cache_removes_exact_names_only() {
+    let dir = spm_cache_dir("spm-cache-remove");
+    let output = stdout(&sass(&dir, &["--spm-cache", "remove", "swift"]));
+    assert!(output.contains("Nothing named swift in the SwiftPM caches"), "{}", output);
+    assert!(cached(&dir, "repositories/swift-argument-parser-1a2b3c4d5e"));
+    assert!(cached(&dir, &format!("artifacts/{}", SWIFT_SYNTAX_ARTIFACT)));
+
+    let output = stdout(&sass(&dir, &["-sc", "remove", "swift-argument-parser"]));
+    assert!(output.contains("Removing repository swift-argument-parser (4 B)"), "{}", output);
+    assert!(!cached(&dir, "repositories/swift-argument-parser-1a2b3c4d5e"));
+    assert!(cached(&dir, "repositories/Kingfisher-9f8e7d6c5b"));
+    assert!(cached(&dir, &format!("artifacts/{}", SWIFT_SYNTAX_ARTIFACT)));
+}
+
+#[test]
+fn spm_cache_prunes_unreferenced_entries() {
+    let dir = spm_cache_dir("spm-cache-prune");
+    let output = stdout(&sass(&dir, &["--spm-cache", "prune", "--dry-run"]));
+    assert!(output.contains(&format!("Would remove artifact {} (4 B)", SWIFT_SYNTAX_ARTIFACT)), "{}", output);
+    assert!(output.contains("Would free 4 B"), "{}", output);
+    assert!(cached(&dir, &format!("artifacts/{}", SWIFT_SYNTAX_ARTIFACT)));
+
+    sass(&dir, &["--spm-cache", "prune"]);
+    assert!(!cached(&dir, &format!("artifacts/{}", SWIFT_SYNTAX_ARTIFACT)));
+    assert!(cached(&dir, "repositories/swift-argument-parser-1a2b3c4d5e"));
+    assert!(cached(&dir, "repositories/Kingfisher-9f8e7d6c5b"));
+}
+
+// A project with the Podfile.lock fixture and its pods installed
+fn pods_dir(name: &str) -> PathBuf {
+    let dir = scratch_dir(name);
+    git_init(&dir);
+    fs::copy(fixture("pods/Podfile.lock"), dir.join("Podfile.lock")).unwrap();
+    for pod in ["Alamofire", "Firebase", "FirebaseCore", "FirebaseCrashlytics", "SnapKit"] {
+        fs::create_dir_all(dir.join("Pods").join(pod)).unwrap();
+    }
+    fake_tool(&dir, "pod", "exit 0");
+    fake_tool(&dir, "gem", "exit 0");
+    dir
+}
+
+#[test]
+fn pods_clean_removes_only_the_named_pods() {
+    let dir = pods_dir("pods-clean");
+    let output = sass(&dir, &["--pods", "clean", "alamofire", "Firebase/Crashlytics", "Nope"]);
+    assert!(output.status.success(), "{}", stderr(&output));
+    assert!(stdout(&output).contains("Nope is not in"), "{}", stdout(&output));
+    assert_eq!(tool_calls(&dir, "pod"), ["cache clean Alamofire --all", "cache clean Firebase --all", "install"]);
+    assert!(!dir.join("Pods/Alamofire").exists());
+    assert!(!dir.join("Pods/Firebase").exists());
+    assert!(dir.join("Pods/FirebaseCore").exists());
+    assert!(dir.join("Pods/SnapKit").exists());
+}
+
+#[test]
+fn pods_clean_from_lockfile_removes_every_pod() {
+    let dir = pods_dir("pods-clean-lockfile");
+    let output = sass(&dir, &["-po", "clean", "--from-lockfile"]);
+    assert!(output.status.success(), "{}", stderr(&output));
+    let calls = tool_calls(&dir, "pod");
+    assert_eq!(calls.len(), 6, "{:?}", calls);
+    assert_eq!(calls.last().unwrap(), "install");
+    assert_eq!(fs::read_dir(dir.join("Pods")).unwrap().count(), 0);
+}
+
+#[test]
+fn pods_checks_by_default() {
+    let dir = pods_dir("pods-default");
+    let output = sass(&dir, &["--pods"]);
+    assert_eq!(output.status.code(), Some(1));
+    assert!(stdout(&output).contains("Pods/Manifest.lock is missing"), "{}", stdout(&output));
+}
+
+#[test]
+fn pods_check_passes_when_in_sync() {
+    let dir = pods_dir("pods-check-in-sync");
+    fs::copy(fixture("pods/Podfile.lock"), dir.join("Pods/Manifest.lock")).unwrap();
+    let output = sass(&dir, &["--pods", "check"]);
+    assert!(output.status.success(), "{}", stderr(&output));
+    assert!(stdout(&output).contains("Pods are in sync with Podfile.lock"), "{}", stdout(&output));
+    assert!(tool_calls(&dir, "pod").is_empty());
+}
+
+#[test]
+fn pods_check_fails_when_out_of_sync() {
+    let dir = pods_dir("pods-check-out-of-sync");
+    fs::copy(fixture("pods/Manifest.lock"), dir.join("Pods/Manifest.lock")).unwrap();
+    let output = sass(&dir, &["--pods", "check"]);
+    assert_eq!(output.status.code(), Some(1));
+    assert!(stdout(&output).contains("  Kingfisher 7.11.0 is installed but not in Podfile.lock"), "{}", stdout(&output));
+    assert!(stderr(&output).contains("run sass --pods check --fix"), "{}", stderr(&output));
+    assert!(tool_calls(&dir, "pod").is_empty());
+}
+
+#[test]
+fn pods_check_fix_reinstalls() {
+    let dir = pods_dir("pods-check-fix");
+    fs::copy(fixture("pods/Manifest.lock"), dir.join("Pods/Manifest.lock")).unwrap();
+    let output = sass(&dir, &["-po", "check", "--fix"]);
+    assert!(output.status.success(), "{}", stderr(&output));
+    assert!(stdout(&output).contains("Repairing pods..."), "{}", stdout(&output));
+    assert_eq!(tool_calls(&dir, "pod"), ["install"]);
+}
+
+// xcrun answering simctl's JSON listings from the fixtures
+fn fake_simctl(dir: &Path) {
+    let script = format!(
+        r#"case "$*" in
+"simctl runtime list -j") cat '{}';;
+"simctl list -j devices devicetypes runtimes") cat '{}';;
+esac"#,
+        fixture("simctl/runtimes.json").display(),
+        fixture("simctl/list.json").display(),
+    );
+    fake_tool(dir, "xcrun", &script);
+}
+
+fn simctl_calls(dir: &Path) -> Vec<String> {
+    tool_calls(dir, "xcrun").into_iter().filter(|call| !call.contains(" -j")).collect()
+}
+
+#[test]
+fn sim_lists_runtimes_by_default() {
+    let dir = scratch_dir("sim-runtimes");
+    fake_simctl(&dir);
+    let output = stdout(&sass(&dir, &["--sim"]));
+    assert!(output.contains("iOS 18.2"), "{}", output);
+    assert!(output.contains("(bundled with Xcode)"), "{}", output);
+    assert!(output.contains("6 runtimes, 33.0 GB total"), "{}", output);
+}
+
+#[test]
+fn sim_runtimes_prune_dry_run_deletes_nothing() {
+    let dir = scratch_dir("sim-prune-dry-run");
+    fake_simctl(&dir);
+    let output = stdout(&sass(&dir, &["--sim", "runtimes", "prune", "--keep-majors", "2", "--dry-run"]));
+    assert!(output.contains("Would delete iOS 16.4 (5.0 GB)"), "{}", output);
+    assert!(output.contains("Would reclaim 5.0 GB"), "{}", output);
+    assert!(simctl_calls(&dir).is_empty());
+}
+
+#[test]
+fn sim_runtimes_prune_deletes_old_runtimes() {
+    let dir = scratch_dir("sim-prune");
+    fake_simctl(&dir);
+    write_config(&dir, "simulator_runtimes_keep = [\"iOS 17\"]\n");
+    let output = sass(&dir, &["-si", "runtimes", "prune", "--keep-majors", "1"]);
+    assert!(output.status.success(), "{}", stderr(&output));
+    assert_eq!(simctl_calls(&dir), [
+        "simctl runtime delete 3D4E5F6A-4444-4B5C-9D2E-3F4A5B6C7D8E",
+        "simctl runtime delete 5F6A7B8C-6666-4D5E-9F4A-5B6C7D8E9F0A",
+    ]);
+}
+
+const SIMULATORS_CONFIG: &str = r#"
+[[simulators]]
+name = "UI Tests iPhone"
+device_type = "iPhone 15"
+runtime = "iOS 17.5"
+
+[[simulators]]
+device_type = "iPad Pro 13-inch (M4)"
+runtime = "iOS 18"
+
+[[simulators]]
+name = "Second UI iPhone"
+device_type = "iPhone 15"
+runtime = "iOS 17.5"
+"#;
+
+#[test]
+fn sim_sync_dry_run_prints_the_plan() {
+    let dir = scratch_dir("sim-sync-dry-run");
+    fake_simctl(&dir);
+    write_config(&dir, SIMULATORS_CONFIG);
+    let output = sass(&dir, &["--sim", "sync", "--dry-run"]);
+    assert!(output.status.success(), "{}", stderr(&output));
+    assert!(stdout(&output).contains("Plan:\n  rename My iPad to iPad Pro 13-inch (M4) (3E4F5A6B-7C8D-4E9F-8A1B-2C3D4E5F6A7B)\n  create Second UI iPhone (iOS 17.5)\n"), "{}", stdout(&output));
+    assert!(simctl_calls(&dir).is_empty());
+}
+
+#[test]
+fn sim_sync_applies_the_plan() {
+    let dir = scratch_dir("sim-sync");
+    fake_simctl(&dir);
+    write_config(&dir, SIMULATORS_CONFIG);
+    let output = sass(&dir, &["-si", "sync", "--delete-undeclared"]);
+    assert!(output.status.success(), "{}", stderr(&output));
+    assert_eq!(simctl_calls(&dir), [
+        "simctl rename 3E4F5A6B-7C8D-4E9F-8A1B-2C3D4E5F6A7B iPad Pro 13-inch (M4)",
+        "simctl create Second UI iPhone com.apple.CoreSimulator.SimDeviceType.iPhone-15 com.apple.CoreSimulator.SimRuntime.iOS-17-5",
+        "simctl delete 2D3E4F5A-6B7C-4D8E-9F0A-1B2C3D4E5F6A",
+        "simctl delete 4F5A6B7C-8D9E-4F0A-9B2C-3D4E5F6A7B8C",
+        "simctl delete 5A6B7C8D-9E0F-4A1B-8C2D-3E4F5A6B7C8D",
+    ]);
+    assert!(stdout(&output).contains("Applied 5 changes."), "{}", stdout(&output));
+}
+
+#[test]
+fn sim_openurl_uses_the_device_flag() {
+    let dir = scratch_dir("sim-openurl");
+    fake_simctl(&dir);
+    write_config(&dir, SIMULATORS_CONFIG);
+    let output = sass(&dir, &["--sim", "openurl", "myapp://settings", "--device", "My iPad"]);
+    assert!(output.status.success(), "{}", stderr(&output));
+    assert_eq!(simctl_calls(&dir), ["simctl openurl 3E4F5A6B-7C8D-4E9F-8A1B-2C3D4E5F6A7B myapp://settings"]);
+}
+
+#[test]
+fn sim_push_uses_the_default_simulator() {
+    let dir = scratch_dir("sim-push");
+    fake_simctl(&dir);
+    write_config(&dir, &format!("default_simulator = \"2D3E4F5A-6B7C-4D8E-9F0A-1B2C3D4E5F6A\"\n{}", SIMULATORS_CONFIG));
+    let output = sass(&dir, &["--sim", "push", "com.example.app", "push.json"]);
+    assert!(output.status.success(), "{}", stderr(&output));
+    assert_eq!(simctl_calls(&dir), ["simctl push 2D3E4F5A-6B7C-4D8E-9F0A-1B2C3D4E5F6A com.example.app push.json"]);
+}
+
+#[test]
+fn sim_privacy_uses_the_first_declared_simulator() {
+    let dir = scratch_dir("sim-privacy");
+    fake_simctl(&dir);
+    write_config(&dir, SIMULATORS_CONFIG);
+    let output = sass(&dir, &["--sim", "privacy", "grant", "photos", "com.example.app"]);
+    assert!(output.status.success(), "{}", stderr(&output));
+    assert_eq!(simctl_calls(&dir), ["simctl privacy 1C2D3E4F-5A6B-4C7D-8E9F-0A1B2C3D4E5F grant photos com.example.app"]);
+}
+
+#[test]
+fn sim_boot_waits_for_the_device() {
+    let dir = scratch_dir("sim-boot");
+    fake_simctl(&dir);
+    let output = sass(&dir, &["-si", "boot", "--device", "UI Tests iPhone"]);
+    assert!(output.status.success(), "{}", stderr(&output));
+    assert_eq!(simctl_calls(&dir), ["simctl bootstatus 1C2D3E4F-5A6B-4C7D-8E9F-0A1B2C3D4E5F -b"]);
+}
+
+#[test]
+fn sim_erase_shuts_down_and_erases() {
+    let dir = scratch_dir("sim-erase");
+    fake_simctl(&dir);
+    write_config(&dir, SIMULATORS_CONFIG);
+    sass(&dir, &["--sim", "erase"]);
+    assert_eq!(simctl_calls(&dir), ["simctl shutdown 1C2D3E4F-5A6B-4C7D-8E9F-0A1B2C3D4E5F", "simctl erase 1C2D3E4F-5A6B-4C7D-8E9F-0A1B2C3D4E5F"]);
+
+    let dir = scratch_dir("sim-erase-all");
+    fake_simctl(&dir);
+    write_config(&dir, SIMULATORS_CONFIG);
+    sass(&dir, &["--sim", "erase", "--all"]);
+    // "Second UI iPhone" isn't installed and the iPad isn't named after its device type yet
+    assert_eq!(simctl_calls(&dir), ["simctl shutdown 1C2D3E4F-5A6B-4C7D-8E9F-0A1B2C3D4E5F", "simctl erase 1C2D3E4F-5A6B-4C7D-8E9F-0A1B2C3D4E5F"]);
+}
+
+#[test]
+fn sim_fails_for_an_unknown_device() {
+    let dir = scratch_dir("sim-unknown-device");
+    fake_simctl(&dir);
+    let output = sass(&dir, &["--sim", "boot", "--device", "Nope"]);
+    assert_eq!(output.status.code(), Some(1));
+    assert_eq!(stderr(&output).trim(), "Error: No simulator named Nope found");
+    assert!(simctl_calls(&dir).is_empty());
+}